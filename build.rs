@@ -10,6 +10,7 @@ const CHARACTERS: u16 = 0x1fe;
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-changed={}", MAP_PATH);
+    println!("cargo:rerun-if-changed={}", SPECIES_PATH);
     write_char_map();
     write_species_map();
 }
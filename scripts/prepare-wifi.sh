@@ -2,6 +2,7 @@
 # SPDX-License-Identifier: GPL-3.0-only
 
 DEV="$1"
+CHANNEL="${2:-7}"
 
 echo "Unmanage $DEV by NetworkManager"
 nmcli device set "$DEV" managed no
@@ -11,5 +12,5 @@ echo "Put device into monitor mode"
 iw "$DEV" set monitor none
 echo "Put device up again"
 ip link set "$DEV" up
-echo "Set to channel 7"
-iw dev "$DEV" set channel 7
\ No newline at end of file
+echo "Set to channel $CHANNEL"
+iw dev "$DEV" set channel "$CHANNEL"
\ No newline at end of file
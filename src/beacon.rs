@@ -1,52 +1,126 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::{fs, thread};
+use std::fs;
 use std::error::Error;
 use std::path::PathBuf;
 use std::time::Duration;
 
-use crc::{Crc, CRC_32_ISO_HDLC};
-
-use crate::{GGID, MacAddress};
-use crate::pcd::{Encrypted, Extended, Partitioned, PCD, PCD_EXTENDED_LENGTH, PCDFragment, PCDHeader, Raw, zero_pad};
+use crate::{GGID, LimitArgs, MacAddress, PacingArgs, PlaylistArgs, RadioArgs};
+use crate::frame::{DistributionPacket, HEADER_FRAGMENT_INDEX, NintendoBeacon};
+use crate::pcd::{Encrypted, Extended, Partitioned, PCD, PCDHeader, Raw, zero_pad};
+use crate::playlist::{Card, Dwell, Interleaved, Playlist, PlaylistEntry, read_playlist};
+use crate::radiotap::{RadioHead, Radiotap};
+use crate::pacing::Pacer;
+use crate::session;
+use crate::sink::FrameSink;
 
 /// A beacon frame generator which can generate an indefinite number of beacon frames.
 pub struct BeaconFrameGenerator {
-    /// The pre-constructed packets without the radio head.
-    beacon_frames: Vec<Vec<u8>>,
-    /// The radio head and the mac addresses.
-    head: [u8; HEAD_LENGTH],
+    /// The beacon frames of all fragments, sequence number and timestamp are updated per frame.
+    beacons: Vec<NintendoBeacon>,
+    /// The radio head preceding each frame.
+    radio_head: RadioHead,
     /// A counter used for sequences.
     counter: u64,
+    /// The interval between two frames in µs, which the timing synchronization function advances by.
+    interval: u64,
+    /// The timing synchronization function of the next frame in µs.
+    tsf: u64,
 }
 
-const HEAD_LENGTH: usize = RADIO_HEAD.len() + BEACON_FRAME.len() + 2 * 6;
-const ADDRESS_OFFSET: usize = RADIO_HEAD.len() + BEACON_FRAME.len();
-const CRC_32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
-
 /// Distributes encrypted packets using the provided parameters.
 ///
 /// This function sends encrypted packets to a network device at a specified interval,
 /// generating beacon frames with appropriate headers.
+/// Multiple cards are distributed one after another, switching after the dwell.
+/// Multiple regions are distributed at once by interleaving their frames, each region with its own address.
+/// The frames are paced in real time for live sinks, otherwise they are handed over with the time they would have been sent at.
+/// The distribution ends once a limit is reached or on SIGINT or SIGTERM, then a summary is printed.
 ///
 /// # Arguments
 ///
-/// * `pcd` - A [PathBuf] representing the path to the PCD file.
-/// * `region` - A [GGID] indicating the region.
-/// * `device` - A [String] representing the network device name.
-/// * `address` - A [MacAddress] representing the Ethernet address.
-/// * `interval` - A [u64] specifying the time interval between sending packets, in microseconds.
+/// * `cards` - The [PlaylistArgs] with the PCD files and the dwell.
+/// * `regions` - The regions to distribute in, besides the regions of the playlist entries.
+///   Cards without own region are distributed in every region.
+/// * `sink` - The [FrameSink] to send the frames to, e.g. a network device or a savefile.
+/// * `address` - A [MacAddress] representing the Ethernet address of the first region, the following regions count up.
+/// * `pacing` - The [PacingArgs] with the time interval between sending packets of a region, in microseconds.
+/// * `radio` - The [RadioArgs] with the Wi-Fi channel, which the device has to be tuned to, and the radiotap header.
+/// * `limits` - The [LimitArgs] ending the distribution, sinks which are not live get 1 round if none is given.
 ///
 /// # Returns
 ///
 /// Returns `Ok(())` if the distribution process runs successfully,
 /// otherwise returns an error wrapped in a `Box<dyn Error>`.
-/// However, this function will actually never terminate on success and will run forever.
+/// However, when sending to a live sink without limits this function only terminates on a signal.
 ///
-pub fn distribute(pcd: PathBuf, region: GGID, device: String, address: MacAddress, interval: u64) -> Result<(), Box<dyn Error>> {
-    let broadcast_addr: MacAddress = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
-    eprintln!("Use device '{}' with ethernet address '{:02x?}' and broadcast address '{:02x?}'", device, address, broadcast_addr);
-    let mut cap = pcap::Capture::from_device(device.as_str())?.open()?;
+pub fn distribute(cards: PlaylistArgs, regions: Vec<GGID>, mut sink: Box<dyn FrameSink>, address: MacAddress, pacing: PacingArgs, radio: RadioArgs, mut limits: LimitArgs) -> Result<(), Box<dyn Error>> {
+    let mut entries: Vec<PlaylistEntry> = cards.pcd.into_iter().map(|pcd| PlaylistEntry { pcd, region: None }).collect();
+    if let Some(p) = cards.playlist {
+        entries.extend(read_playlist(&p)?);
+    }
+    if entries.is_empty() {
+        return Err("No wondercard to distribute".into());
+    }
+    let dwell = cards.dwell_rounds.map(Dwell::Rounds).unwrap_or(Dwell::Time(cards.dwell * 1_000_000));
+    let mut all_regions: Vec<GGID> = vec![];
+    for r in regions.into_iter().chain(entries.iter().filter_map(|e| e.region)) {
+        if !all_regions.contains(&r) {
+            all_regions.push(r);
+        }
+    }
+    if all_regions.is_empty() {
+        return Err("No region given for the distribution".into());
+    }
+    let playlists = all_regions.iter().enumerate().map(|(i, &r)| {
+        let region_address = region_address(address, i);
+        let region_entries: Vec<PlaylistEntry> = entries.iter().filter(|e| e.region.is_none_or(|er| er == r)).cloned().collect();
+        if all_regions.len() > 1 {
+            eprintln!("Region '{}' uses ethernet address '{:02x?}'", r, region_address);
+        }
+        open_playlist(region_entries, r, region_address, &radio, pacing.interval, dwell)
+    }).collect::<Result<Vec<Playlist>, Box<dyn Error>>>()?;
+    let mut interleaved = Interleaved::new(playlists);
+    // the interval applies to each region, so the frames of all regions are spread across it
+    let frame_interval = pacing.interval / all_regions.len() as u64;
+    if !sink.is_live() && limits.is_empty() {
+        limits.rounds = Some(1);
+    }
+    match interleaved.playlists() {
+        [playlist] if playlist.cards().len() == 1 => eprintln!("Distributing in {} µs intervals on channel {} for region '{}'...", pacing.interval, radio.channel, playlist.cards()[0].region),
+        [playlist] => eprintln!("Distributing {} cards in {} µs intervals on channel {}, switching after {}...", playlist.cards().len(), pacing.interval, radio.channel, dwell),
+        playlists => eprintln!("Distributing {} regions in {} µs intervals on channel {}, switching cards after {}...", playlists.len(), pacing.interval, radio.channel, dwell),
+    }
+    let pacer = sink.is_live().then(|| Pacer::new(Duration::from_micros(frame_interval), Duration::from_micros(pacing.busy_wait)));
+    let summary = session::run(&mut interleaved, &limits, frame_interval, pacer, sink.as_mut())?;
+    sink.flush()?;
+    interleaved.playlists().iter().flat_map(Playlist::cards).for_each(Card::log_progress);
+    eprintln!("{}", summary);
+    Ok(())
+}
+
+/// The address of the region with the index, the last octet is counted up from the given address.
+fn region_address(address: MacAddress, index: usize) -> MacAddress {
+    let mut region_address = address;
+    region_address[5] = address[5].wrapping_add(index as u8);
+    region_address
+}
+
+/// Opens all cards of the playlist for the region.
+fn open_playlist(entries: Vec<PlaylistEntry>, region: GGID, address: MacAddress, radio: &RadioArgs, interval: u64, dwell: Dwell) -> Result<Playlist, Box<dyn Error>> {
+    if entries.is_empty() {
+        return Err(format!("No wondercard given for region '{}'", region).into());
+    }
+    let cards = entries.into_iter().map(|e| {
+        let name = e.pcd.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let generator = open_generator(e.pcd, region, address, radio, interval)?;
+        Ok(Card { name, region, generator, rounds: 0 })
+    }).collect::<Result<Vec<Card>, Box<dyn Error>>>()?;
+    Ok(Playlist::new(cards, dwell, interval))
+}
+
+/// Reads and encrypts a PCD file and creates the [BeaconFrameGenerator] distributing it.
+fn open_generator(pcd: PathBuf, region: GGID, address: MacAddress, radio: &RadioArgs, interval: u64) -> Result<BeaconFrameGenerator, Box<dyn Error>> {
     eprintln!("Open wondercard file '{}'", pcd.as_path().display());
     let pcd: PCD<Raw> = PCD::try_from(fs::read(pcd)?.as_slice())?;
     let partitioned: PCD<Partitioned> = pcd.into();
@@ -55,13 +129,8 @@ pub fn distribute(pcd: PathBuf, region: GGID, device: String, address: MacAddres
     let checksum = extended.checksum()?;
     eprintln!("Wondercard has checksum {:04x}", checksum);
     let encrypted = extended.encrypt(&address)?;
-    let generator = BeaconFrameGenerator::new(address, region, &encrypted, header, checksum);
-    eprintln!("Distributing in {} µs intervals for region '{}'...", interval, region);
-    for packet in generator {
-        cap.sendpacket(packet.as_slice())?;
-        thread::sleep(Duration::from_micros(interval));
-    }
-    Ok(())
+    let radio_head = RadioHead::new(radio.radiotap, radio.channel, radio.tx_power)?;
+    Ok(BeaconFrameGenerator::new(address, region, radio.channel, interval, &encrypted, header, checksum).with_radio_head(radio_head))
 }
 
 impl BeaconFrameGenerator {
@@ -74,6 +143,8 @@ impl BeaconFrameGenerator {
     ///
     /// * `address` - A [MacAddress] representing the Ethernet address.
     /// * `region` - A [GGID] indicating the region.
+    /// * `channel` - The Wi-Fi channel from 1 to 14.
+    /// * `interval` - The interval between two frames in µs, used for the timestamps.
     /// * `pcd` - A reference to the encrypted PCD data.
     /// * `header` - A [PCDHeader] representing the PCD header.
     /// * `checksum` - A [u16] representing the checksum of the extended PCD.
@@ -82,21 +153,37 @@ impl BeaconFrameGenerator {
     ///
     /// Returns a new [BeaconFrameGenerator] instance.
     ///
-    pub fn new(address: MacAddress, region: GGID, pcd: &PCD<Encrypted>, header: PCDHeader, checksum: u16) -> Self {
+    pub fn new(address: MacAddress, region: GGID, channel: u8, interval: u64, pcd: &PCD<Encrypted>, header: PCDHeader, checksum: u16) -> Self {
         let mut fragments = pcd.fragments();
         fragments.push(zero_pad(header));
-        let beacon_frames = (0..fragments.len()).map(|f| wireless_management(packet(fragments.len() as u32, f as u16, checksum, PCD_EXTENDED_LENGTH as u32, *fragments.get(f).unwrap(), region))).collect();
-        let mut head: [u8; HEAD_LENGTH] = [0; HEAD_LENGTH];
-        head[..RADIO_HEAD.len()].copy_from_slice(&RADIO_HEAD);
-        head[RADIO_HEAD.len()..ADDRESS_OFFSET].copy_from_slice(&BEACON_FRAME);
-        head[ADDRESS_OFFSET..ADDRESS_OFFSET + 6].copy_from_slice(&address);
-        head[ADDRESS_OFFSET + 6..ADDRESS_OFFSET + 12].copy_from_slice(&address);
+        let beacons = fragments.iter().enumerate().map(|(i, &fragment)| {
+            let index = if i == fragments.len() - 1 { HEADER_FRAGMENT_INDEX } else { i as u16 };
+            NintendoBeacon::new(address, channel, DistributionPacket::new(region as u32, checksum, index, fragment))
+        }).collect();
         Self {
-            beacon_frames,
-            head,
+            beacons,
+            radio_head: RadioHead::new(Radiotap::Legacy, channel, None).unwrap(),
             counter: 0,
+            interval,
+            tsf: 0,
         }
     }
+
+    /// Replaces the radio head, which is the legacy radiotap header by default.
+    pub fn with_radio_head(mut self, radio_head: RadioHead) -> Self {
+        self.radio_head = radio_head;
+        self
+    }
+
+    /// Continues the timestamps at `tsf` µs, e.g. after another generator was used meanwhile.
+    pub fn set_tsf(&mut self, tsf: u64) {
+        self.tsf = tsf;
+    }
+
+    /// The number of frames of one complete distribution.
+    pub fn round_length(&self) -> usize {
+        self.beacons.len()
+    }
 }
 
 impl Iterator for BeaconFrameGenerator {
@@ -105,8 +192,8 @@ impl Iterator for BeaconFrameGenerator {
     /// Generates the next beacon frame packet.
     ///
     /// This method generates the next beacon frame packet in the sequence,
-    /// combining the beacon frame header, sequence information, fragment data,
-    /// and CRC checksum.
+    /// the [NintendoBeacon] of the fragment with the sequence number and timestamp preceded by the radio head.
+    /// The timestamps start at 0 and advance by the interval with each frame.
     ///
     /// # Returns
     ///
@@ -115,111 +202,63 @@ impl Iterator for BeaconFrameGenerator {
     /// However, this should actually never happen.
     ///
     fn next(&mut self) -> Option<Self::Item> {
-        let sequence = (self.counter << 4).to_le_bytes();
-        let next = self.beacon_frames.get(self.counter as usize % self.beacon_frames.len()).map(|fragment| {
-            let packet: Vec<u8> = [
-                &self.head,
-                &sequence[..2],
-                fragment
-            ].concat();
-            let crc_checksum = CRC_32.checksum(&packet[RADIO_HEAD.len()..]);
+        let tsf = self.tsf;
+        let count = self.beacons.len();
+        let next = self.beacons.get_mut(self.counter as usize % count).map(|beacon| {
+            beacon.sequence_control = (self.counter << 4) as u16;
+            beacon.timestamp = tsf;
             [
-                packet.as_slice(),
-                &crc_checksum.to_le_bytes()
+                self.radio_head.with_timestamp(tsf),
+                beacon.serialize(),
             ].concat()
         });
         self.counter += 1;
+        self.tsf += self.interval;
         next
     }
 }
 
-const RADIO_HEAD: [u8; 56] = [
-    0x00, 0x00, // rev, pad
-    0x38, 0x00, // header length
-    0x2f, 0x40, 0x40, 0xa0, 0x20, 0x08, 0x00, 0xa0, 0x20, 0x08, 0x00, 0x00, // present flags
-    0x4d, 0x6c, 0xb8, 0x06, 0x00, 0x00, 0x00, 0x00, // MAC timestamp, update
-    0x12, // flags
-    0x04, // data rate
-    0x8a, 0x09, // channel frequency
-    0xa0, 0x00, // channel flags
-    0xbd, // antenna signal
-    0x00, // ?
-    0x00, 0x00, // rx flags
-    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // ?
-    0xee, 0x6b, 0xb8, 0x06, 0x00, 0x00, 0x00, 0x00, 0x16, 0x00, 0x11, 0x03, // timestamp information, update
-    0xbc, // antenna signal
-    0x00, // antenna
-    0xbd, // antenna signal
-    0x01 // antenna
-];
-
-const BEACON_FRAME: [u8; 10] = [
-    0x80, 0x00, // frame control field (type, subtype)
-    0x00, 0x00, // duration
-    0xff, 0xff, 0xff, 0xff, 0xff, 0xff // destination address
-];
-
-//    0xa4, 0xc0, 0xe1, 0x6e, 0x76, 0x80, // source address
-//    0xa4, 0xc0, 0xe1, 0x6e, 0x76, 0x80, // bssid
-// sequence number, u16
-
-const WIRELESS_MANAGEMENT: [u8; 32] = [
-// fixed parameters
-    0xcc, 0xc8, 0x08, 0x2f, 0x00, 0x00, 0x00, 0x00, // timestamp, update
-    0x0a, 0x00, // beacon interval
-    0x21, 0x00, // capabilities information
-// tagged parameters
-    0x01, 0x02, 0x82, 0x84, // tag supported rates
-    0x03, 0x01, 0x07, // ds parameter set, current channel
-    0x05, 0x05, 0x01, 0x02, 0x00, 0x00, 0x00, // traffic indication map, update
-// vendor specific
-    0xdd, // tag number
-    0x88, // tag length
-    0x00, 0x09, 0xbf, // OUI
-    0x00, // OUI type
-];
-
-// packet
-
-/// Creates a packet for the given beacon frame parameters.
-///
-/// This function constructs a packet using the provided parameters for the
-/// beacon frame, including frames count, fragment index, checksum, payload
-/// length, packet payload, and GGID.
-///
-/// # Arguments
-///
-/// * `frames_count` - The total number of frames in the beacon frame sequence.
-/// * `fragment_index` - The index of the current fragment within the sequence.
-/// * `checksum` - The checksum value of the packet.
-/// * `payload_length` - The length of the packet payload.
-/// * `packet_payload` - The payload data of the packet.
-/// * `ggid` - A [GGID] indicating the region.
-///
-/// # Returns
-///
-/// Returns a [Vec<u8>] containing the constructed packet.
-///
-fn packet(frames_count: u32, fragment_index: u16, checksum: u16, payload_length: u32, packet_payload: PCDFragment, ggid: GGID) -> Vec<u8> {
-    [
-        frames_count.to_le_bytes().as_slice(),
-        &0x1u16.to_le_bytes(),
-        &0x1u16.to_le_bytes(),
-        &(ggid as u32).to_le_bytes(),
-        &0x0u16.to_le_bytes(),
-        &0x70u16.to_le_bytes(),
-        &0x28u16.to_le_bytes(),
-        &0xcu16.to_le_bytes(),
-        &checksum.to_le_bytes(),
-        &(if fragment_index == (frames_count - 1) as u16 { 0xffff } else { fragment_index }).to_le_bytes(),
-        &payload_length.to_le_bytes(),
-        &packet_payload
-    ].concat()
+/// The address of the generators for tests.
+#[cfg(test)]
+pub(crate) const TEST_ADDRESS: MacAddress = [0xa4, 0xc0, 0xe1, 0x6e, 0x76, 0x80];
+
+/// The generator of an empty wondercard on channel 7 with an interval of 10240 µs for tests.
+#[cfg(test)]
+pub(crate) fn test_generator(region: GGID) -> BeaconFrameGenerator {
+    test_generator_with(&[0u8; crate::pcd::PCD_LENGTH], region)
 }
 
-fn wireless_management(packet: Vec<u8>) -> Vec<u8> {
-    [
-        WIRELESS_MANAGEMENT.as_slice(),
-        &packet
-    ].concat()
+/// Like [test_generator], but distributes the wondercard `data`.
+#[cfg(test)]
+pub(crate) fn test_generator_with(data: &[u8; crate::pcd::PCD_LENGTH], region: GGID) -> BeaconFrameGenerator {
+    let pcd: PCD<Raw> = PCD::try_from(data.as_slice()).unwrap();
+    let partitioned: PCD<Partitioned> = pcd.into();
+    let header = partitioned.header();
+    let extended: PCD<Extended> = partitioned.into();
+    let checksum = extended.checksum().unwrap();
+    let encrypted = extended.encrypt(&TEST_ADDRESS).unwrap();
+    BeaconFrameGenerator::new(TEST_ADDRESS, region, 7, 10240, &encrypted, header, checksum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::radiotap;
+
+    #[test]
+    fn advancing_timestamps() {
+        let generator = test_generator(GGID::English);
+
+        for (i, frame) in generator.take(25).enumerate() {
+            let tsf = (i as u64 * 10240).to_le_bytes();
+            // MAC timestamp and timestamp information of the legacy radio head
+            assert_eq!(tsf, frame[16..24]);
+            assert_eq!(tsf, frame[40..48]);
+            let (frame, fcs) = radiotap::strip(&frame).unwrap();
+            let beacon = NintendoBeacon::parse(frame, fcs).unwrap();
+            assert_eq!(i as u64 * 10240, beacon.timestamp);
+            assert_eq!((i as u16) << 4, beacon.sequence_control);
+            assert_eq!(if i % 10 == 9 { HEADER_FRAGMENT_INDEX } else { i as u16 % 10 }, beacon.packet.fragment_index);
+        }
+    }
 }
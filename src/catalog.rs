@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::error::Error;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::date;
+use crate::error::err_reason;
+use crate::export::Icon;
+use crate::pcd::{CardType, Deserialized, Game, PCD, Partitioned, Raw};
+
+/// File extensions of wondercards in the PCD format.
+const PCD_EXTENSIONS: [&str; 2] = ["pcd", "wc4"];
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum CatalogFormat {
+    Csv,
+    Json,
+}
+
+/// A wondercard of the catalog.
+#[derive(Serialize)]
+pub struct Entry {
+    /// Path relative to the cataloged directory
+    pub path: PathBuf,
+    pub title: String,
+    pub card_id: u16,
+    pub card_type: CardType,
+    pub gift_instance: u16,
+    pub games: Vec<Game>,
+    pub icons: Vec<Icon>,
+    /// Received date as `YYYY-MM-DD`
+    pub received: String,
+    /// SHA-256 of the file as hexadecimal, equal for identical cards in different places
+    pub sha256: String,
+}
+
+impl Entry {
+    fn new(path: PathBuf, data: &[u8], pcd: &PCD<Deserialized>) -> Self {
+        let des = &pcd.state;
+        Entry {
+            path,
+            title: des.title.clone(),
+            card_id: des.card_id,
+            card_type: des.card_type,
+            gift_instance: des.gift_instance,
+            games: des.games.clone(),
+            icons: vec![des.icons.0.into(), des.icons.1.into(), des.icons.2.into()],
+            received: date::fmt(pcd.received()),
+            sha256: Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect(),
+        }
+    }
+
+    /// The fields as CSV record, lists are separated by spaces.
+    fn record(&self) -> [String; 9] {
+        [
+            self.path.display().to_string(),
+            self.title.clone(),
+            self.card_id.to_string(),
+            format!("{:?}", self.card_type),
+            self.gift_instance.to_string(),
+            self.games.iter().map(|g| format!("{:?}", g)).collect::<Vec<String>>().join(" "),
+            self.icons.iter().map(|i| i.pokedex.to_string()).collect::<Vec<String>>().join(" "),
+            self.received.clone(),
+            self.sha256.clone(),
+        ]
+    }
+}
+
+/// Column names of the CSV catalog in the order of [Entry::record].
+const CSV_HEADER: [&str; 9] = ["path", "title", "card_id", "card_type", "gift_instance", "games", "icons", "received", "sha256"];
+
+/// Collects the files with a [PCD_EXTENSIONS] extension below `dir` recursively, hidden entries are ignored.
+/// Symbolic links to directories are not followed, so link loops cannot recurse endlessly.
+fn find_cards(dir: &Path, cards: &mut Vec<PathBuf>) -> Result<(), Box<dyn Error>> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with('.')) {
+            continue;
+        }
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() && path.is_dir() {
+            eprintln!("Warning: skipping linked directory '{}'", path.display());
+        } else if file_type.is_dir() {
+            if let Err(e) = find_cards(&path, cards) {
+                eprintln!("Warning: skipping directory '{}': {}", path.display(), e);
+            }
+        } else if path.extension().and_then(|e| e.to_str()).is_some_and(|e| PCD_EXTENSIONS.iter().any(|x| e.eq_ignore_ascii_case(x))) {
+            cards.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Reads a PCD file and deserializes it.
+fn read_entry(dir: &Path, path: &Path) -> Result<Entry, Box<dyn Error>> {
+    let data = fs::read(path)?;
+    let raw: PCD<Raw> = PCD::try_from(data.as_slice())?;
+    let des = PCD::<Partitioned>::from(raw).deserialize();
+    Ok(Entry::new(path.strip_prefix(dir).unwrap_or(path).to_path_buf(), &data, &des))
+}
+
+/// Builds an index of all PCD files in a directory tree, e.g. a clone of the EventsGallery.
+///
+/// Files which are no valid PCD are skipped with a warning.
+///
+/// # Arguments
+///
+/// * `dir` - A [PathBuf] representing the directory to search for `.pcd` and `.wc4` files.
+/// * `format` - The [CatalogFormat] of the index.
+/// * `output` - The location of the index, printed to stdout if none is given.
+///
+/// # Returns
+///
+/// Returns [Ok(())] if the index could be written,
+/// otherwise returns an error wrapped in a [Box<dyn Error>].
+///
+pub fn catalog(dir: PathBuf, format: CatalogFormat, output: Option<PathBuf>) -> Result<(), Box<dyn Error>> {
+    let mut files = Vec::new();
+    find_cards(&dir, &mut files).map_err(|e| format!("Unable to read directory '{}': {}", dir.display(), e))?;
+    files.sort();
+    let entries: Vec<Entry> = files.iter()
+        .filter_map(|f| read_entry(&dir, f).map_err(|e| eprintln!("Warning: skipping '{}': {}", f.display(), e)).ok())
+        .collect();
+    eprintln!("Cataloged {} of {} files", entries.len(), files.len());
+
+    let writer: Box<dyn Write> = match output {
+        Some(o) => Box::new(fs::File::create(o).map_err(|e| err_reason("Unable to create catalog", e))?),
+        None => Box::new(std::io::stdout().lock()),
+    };
+    write_catalog(&entries, format, writer)
+}
+
+fn write_catalog<W: Write>(entries: &[Entry], format: CatalogFormat, mut writer: W) -> Result<(), Box<dyn Error>> {
+    match format {
+        CatalogFormat::Csv => {
+            let mut csv = csv::Writer::from_writer(writer);
+            csv.write_record(CSV_HEADER)?;
+            for entry in entries {
+                csv.write_record(entry.record())?;
+            }
+            csv.flush()?;
+        }
+        CatalogFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, entries)?;
+            writeln!(writer)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcd::PCD_LENGTH;
+
+    /// Removes the directory even if the test fails.
+    struct TempDir(PathBuf);
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn catalog_directory() {
+        let temp = TempDir(std::env::temp_dir().join(format!("wc-beacon-catalog-{}", std::process::id())));
+        let dir = temp.0.clone();
+        fs::create_dir_all(dir.join("Gen 4")).unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&dir, dir.join("Gen 4").join("loop")).unwrap();
+        let mut pcd = PCD::<Deserialized>::new();
+        pcd.state.title = "Pikachu, Gift".to_string();
+        pcd.state.games = vec![Game::Diamond, Game::Pearl];
+        pcd.state.icons = (25, 0, 0);
+        let raw: PCD<Raw> = (&pcd.serialize()).into();
+        let data: [u8; PCD_LENGTH] = raw.into();
+        fs::write(dir.join("Gen 4").join("pikachu.pcd"), data).unwrap();
+        fs::write(dir.join("broken.pcd"), [0u8; 16]).unwrap();
+        fs::write(dir.join("notes.txt"), "not a card").unwrap();
+
+        let mut files = Vec::new();
+        find_cards(&dir, &mut files).unwrap();
+        files.sort();
+        assert_eq!(vec![dir.join("Gen 4").join("pikachu.pcd"), dir.join("broken.pcd")], files);
+        assert!(read_entry(&dir, &files[1]).is_err());
+        let entry = read_entry(&dir, &files[0]).unwrap();
+
+        let mut csv = Vec::new();
+        write_catalog(&[entry], CatalogFormat::Csv, &mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(Some("path,title,card_id,card_type,gift_instance,games,icons,received,sha256"), lines.next());
+        let row = lines.next().unwrap();
+        assert!(row.starts_with("Gen 4/pikachu.pcd,\"Pikachu, Gift\",0,"));
+        assert!(row.contains(",Diamond Pearl,25 0 0,"));
+    }
+}
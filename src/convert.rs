@@ -0,0 +1,56 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::error::err_reason;
+use crate::pcd::{Partitioned, PCD, PCD_LENGTH, Raw};
+use crate::pgt::PGT;
+
+/// Extracts the PGT of a PCD file and saves it to a new file.
+///
+/// # Arguments
+///
+/// * `pcd_file` - A [PathBuf] representing the path to the PCD file.
+/// * `pgt_file` - A [PathBuf] representing the path to the output PGT file.
+///
+/// # Returns
+///
+/// Returns [Ok(())] if the conversion and file write process runs successfully,
+/// otherwise returns an error wrapped in a [Box<dyn Error>].
+///
+pub fn pcd_to_pgt(pcd_file: PathBuf, pgt_file: PathBuf) -> Result<(), Box<dyn Error>> {
+    let data = fs::read(pcd_file).map_err(|e| err_reason("Unable to read pcd file", e))?;
+    let pcd: PCD<Raw> = PCD::try_from(data.as_slice())?;
+    let pgt = PGT::from(&PCD::<Partitioned>::from(pcd).deserialize());
+    fs::write(pgt_file, pgt.data()).map_err(|e| err_reason("Unable to write pgt file", e))?;
+    Ok(())
+}
+
+/// Wraps a PGT file into a PCD and saves it to a new file.
+///
+/// Title, description and icons are generated, the card can be received by all games.
+/// Use `set` afterwards to adjust them.
+///
+/// # Arguments
+///
+/// * `pgt_file` - A [PathBuf] representing the path to the PGT file.
+/// * `card_id` - The Wonder Card ID, the gift instance is used if none is given.
+/// * `pcd_file` - A [PathBuf] representing the path to the output PCD file.
+///
+/// # Returns
+///
+/// Returns [Ok(())] if the conversion and file write process runs successfully,
+/// otherwise returns an error wrapped in a [Box<dyn Error>].
+///
+pub fn pgt_to_pcd(pgt_file: PathBuf, card_id: Option<u16>, pcd_file: PathBuf) -> Result<(), Box<dyn Error>> {
+    let data = fs::read(pgt_file).map_err(|e| err_reason("Unable to read pgt file", e))?;
+    let pgt = PGT::try_from(data.as_slice())?;
+    let mut pcd = pgt.wrap();
+    pcd.state.card_id = card_id.unwrap_or(pcd.state.gift_instance);
+    let pcd: PCD<Raw> = (&pcd.serialize()).into();
+    let pcd_data: [u8; PCD_LENGTH] = pcd.into();
+    fs::write(pcd_file, pcd_data).map_err(|e| err_reason("Unable to write pcd file", e))?;
+    Ok(())
+}
@@ -6,20 +6,22 @@ use std::fs::File;
 use std::io::{Read, Write};
 use std::path::PathBuf;
 use crate::error::err_reason;
-use crate::pcd::{CardType, Deserialized, Game, Partitioned, PCD, PCD_LENGTH, pgt_info, Raw};
+use crate::pcd::{Deserialized, Partitioned, PCD, PCD_LENGTH, PCD_PADDING_LENGTH, pgt_info, Raw};
+use crate::pgf::{PGF, PGF_LENGTH, PGFCardType};
+use crate::date;
+use crate::{CardArgs, PokemonArgs, WondercardArgs};
+use crate::pk4::{pgt_pokemon, put_pgt_pokemon};
 
 
-pub fn info(pcd: PathBuf) -> Result<(), Box<dyn Error>> {
-    let pcd: PCD<Raw> = PCD::try_from(fs::read(pcd)?.as_slice())?;
-    let partitioned: PCD<Partitioned> = pcd.into();
-    let des = partitioned.deserialize();
-    eprintln!("{}", des);
-    Ok(())
-}
-
-pub fn set(title: Option<String>, card_type: Option<CardType>, card_id: Option<u16>, gift_instance: Option<u16>, games: Option<Vec<Game>>, comment: Option<String>, redistribution: Option<u8>, icons: Option<Vec<u16>>, pgt: Option<PathBuf>, received: Option<u16>, pcd: Option<PathBuf>, output: PathBuf) -> Result<(), Box<dyn Error>> {
+pub fn set(fields: WondercardArgs, card: CardArgs, pokemon: PokemonArgs, pcd: Option<PathBuf>, output: PathBuf) -> Result<(), Box<dyn Error>> {
     let mut pcd = if let Some(f) = pcd {
         let data = fs::read(f).map_err(|e| err_reason("Unable to read pcd file", e))?;
+        if data.len() == PGF_LENGTH {
+            if fields.has_pcd_only() || !card.is_empty() {
+                return Err("Only title, card id, gen5 games, date and pokemon fields can be set for PGF files".into());
+            }
+            return set_pgf(&data, fields, pokemon, output);
+        }
         let raw: PCD<Raw> = PCD::try_from(data.as_slice())?;
         let parts: PCD<Partitioned> = PCD::from(raw);
         parts.deserialize()
@@ -27,35 +29,33 @@ pub fn set(title: Option<String>, card_type: Option<CardType>, card_id: Option<u
         PCD::<Deserialized>::new()
     };
 
-    if let Some(t) = title {
-        pcd.state.title = t;
+    if fields.gen5_games.is_some() {
+        return Err("Generation V games can only be set for PGF files".into());
     }
 
-    if let Some(c) = card_id {
-        pcd.state.card_id = c;
-    }
+    edit_common(&fields, &mut pcd.state.title, &mut pcd.state.card_id);
 
-    if let Some(g) = games {
+    if let Some(g) = fields.games {
         pcd.state.games = g;
     }
 
-    if let Some(c) = comment {
+    if let Some(c) = fields.description {
         pcd.state.comment = c;
     }
 
-    if let Some(r) = redistribution {
+    if let Some(r) = fields.redistribution {
         pcd.state.redistribution = r;
     }
 
-    if let Some(i) = icons {
+    if let Some(i) = fields.icons {
         pcd.state.icons = (i[0], i[1], i[2]);
     }
 
-    if let Some(r) = received {
+    if let Some(r) = fields.date {
         pcd.state.received = r;
     }
 
-    if let Some(p) = pgt {
+    if let Some(p) = fields.pgt {
         let mut f = File::open(p).map_err(|e| err_reason("Unable to read pgt", e))?;
         f.read_exact(&mut pcd.state.pgt).map_err(|e| err_reason("Unable to read pgt", e))?;
         match f.read(&mut [0]) {
@@ -68,14 +68,40 @@ pub fn set(title: Option<String>, card_type: Option<CardType>, card_id: Option<u
         pcd.state.gift_instance = gift_instance;
     }
 
-    if let Some(c) = card_type {
+    if let Some(c) = fields.kind {
         pcd.state.card_type = c;
     }
 
-    if let Some(gift_instance) = gift_instance {
+    if let Some(gift_instance) = fields.gift_instance {
         pcd.state.gift_instance = gift_instance;
     }
 
+    if let Some(s) = card.slot {
+        pcd.state.slot = s;
+    }
+
+    if let Some(u) = card.used {
+        pcd.state.used = u;
+    }
+
+    if let Some(s) = card.shareable {
+        pcd.state.shareable = s;
+    }
+
+    if let Some(f) = card.card_flags {
+        pcd.state.card_flags = f;
+    }
+
+    if let Some(d) = card.delivery {
+        pcd.state.delivery = d;
+    }
+
+    if card.clear_padding {
+        pcd.state.padding = [0; PCD_PADDING_LENGTH];
+    }
+
+    edit_pokemon(&mut pcd.state, pokemon)?;
+
     let pcd: PCD<Raw> = (&pcd.serialize()).try_into()?;
     let pcd_data: [u8; PCD_LENGTH] = pcd.into();
 
@@ -83,4 +109,158 @@ pub fn set(title: Option<String>, card_type: Option<CardType>, card_id: Option<u
     f.write(&pcd_data).map_err(|e| err_reason("Unable to write pcd file", e))?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Applies the [PokemonArgs] to the PK4 embedded in the pgt and re-encrypts it.
+/// Does nothing if no Pokémon field is to be changed.
+fn edit_pokemon(pcd: &mut Deserialized, args: PokemonArgs) -> Result<(), Box<dyn Error>> {
+    if args.is_empty() {
+        return Ok(());
+    }
+    let mut pk = pgt_pokemon(&pcd.pgt, pcd.card_type).ok_or_else(|| format!("Card type {:?} does not contain a pokemon", pcd.card_type))?.decrypt().deserialize();
+
+    if let Some(s) = args.species {
+        pk.set_species(s);
+    }
+
+    if let Some(n) = &args.nickname {
+        pk.state.nickname = n.clone();
+        pk.state.is_nicknamed = true;
+    }
+
+    if let Some(l) = args.level {
+        pk.state.met_level = l;
+        pk.state.level = l;
+    }
+
+    if let Some(n) = args.nature {
+        pk.set_nature(n);
+    }
+
+    if let Some(r) = &args.ribbons {
+        if r.len() != 3 {
+            return Err(format!("Exactly 3 ribbon bitfields are required, but {} were specified", r.len()).into());
+        }
+        pk.state.ribbons = (r[0], r[1], r[2]);
+    }
+
+    let des = &mut pk.state;
+    edit_pokemon_fields(&args, PokemonFields { ot_name: &mut des.ot_name, ot_id: &mut des.ot_id, moves: &mut des.moves, held_item: &mut des.held_item, ball: &mut des.ball, language: &mut des.language, ivs: &mut des.ivs })?;
+
+    put_pgt_pokemon(&mut pcd.pgt, &pk.serialize().encrypt());
+    Ok(())
+}
+
+fn moves(m: &[u16]) -> Result<[u16; 4], String> {
+    if m.len() > 4 {
+        return Err(format!("A pokemon can only have 4 moves, but {} were specified", m.len()));
+    }
+    let mut moves = [0; 4];
+    moves[..m.len()].copy_from_slice(m);
+    Ok(moves)
+}
+
+fn ivs(i: &[u8]) -> Result<[u8; 6], String> {
+    i.try_into().map_err(|_| format!("Exactly 6 IVs are required, but {} were specified", i.len()))
+}
+
+/// Edits a generation V wonder card, the counterpart of [set] for PGF files.
+fn set_pgf(data: &[u8], fields: WondercardArgs, args: PokemonArgs, output: PathBuf) -> Result<(), Box<dyn Error>> {
+    let mut pgf = PGF::try_from(data)?.deserialize();
+
+    edit_common(&fields, &mut pgf.state.title, &mut pgf.state.card_id);
+
+    if let Some(g) = fields.gen5_games {
+        pgf.state.games = g;
+    }
+
+    if let Some(r) = fields.date {
+        pgf.state.received = date::from_days(r);
+    }
+
+    if !args.is_empty() && pgf.state.card_type != Some(PGFCardType::Pokemon) {
+        return Err("Card does not contain a pokemon".into());
+    }
+
+    if args.ribbons.is_some() {
+        return Err("Ribbons can only be set for PCD files".into());
+    }
+
+    let pk = &mut pgf.state.pokemon;
+
+    if let Some(s) = args.species {
+        pk.species = s;
+    }
+
+    if let Some(n) = &args.nickname {
+        pk.nickname = n.clone();
+    }
+
+    if let Some(l) = args.level {
+        pk.level = l;
+    }
+
+    if let Some(n) = args.nature {
+        pk.nature = n;
+    }
+
+    edit_pokemon_fields(&args, PokemonFields { ot_name: &mut pk.ot_name, ot_id: &mut pk.ot_id, moves: &mut pk.moves, held_item: &mut pk.held_item, ball: &mut pk.ball, language: &mut pk.language, ivs: &mut pk.ivs })?;
+
+    let pgf_data: [u8; PGF_LENGTH] = pgf.serialize().into();
+    fs::write(output, pgf_data).map_err(|e| err_reason("Unable to write pgf file", e))?;
+    Ok(())
+}
+
+/// Applies the fields PCD and PGF files have in common.
+fn edit_common(fields: &WondercardArgs, title: &mut String, card_id: &mut u16) {
+    if let Some(t) = &fields.title {
+        *title = t.clone();
+    }
+
+    if let Some(c) = fields.card_id {
+        *card_id = c;
+    }
+}
+
+/// The Pokémon fields PK4 and PGF have in common, which are edited the same way.
+struct PokemonFields<'a> {
+    ot_name: &'a mut String,
+    ot_id: &'a mut u16,
+    moves: &'a mut [u16; 4],
+    held_item: &'a mut u16,
+    ball: &'a mut u8,
+    language: &'a mut u8,
+    ivs: &'a mut [u8; 6],
+}
+
+/// Applies the [PokemonArgs] to the [PokemonFields], the remaining fields depend on the format.
+fn edit_pokemon_fields(args: &PokemonArgs, pk: PokemonFields) -> Result<(), String> {
+    if let Some(n) = &args.ot_name {
+        *pk.ot_name = n.clone();
+    }
+
+    if let Some(i) = args.ot_id {
+        *pk.ot_id = i;
+    }
+
+    if let Some(m) = &args.moves {
+        *pk.moves = moves(m)?;
+    }
+
+    if let Some(i) = args.held_item {
+        *pk.held_item = i;
+    }
+
+    if let Some(b) = args.ball {
+        *pk.ball = b;
+    }
+
+    if let Some(l) = args.language {
+        *pk.language = l;
+    }
+
+    if let Some(i) = &args.ivs {
+        *pk.ivs = ivs(i)?;
+    }
+    Ok(())
+}
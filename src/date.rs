@@ -0,0 +1,213 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Days from 1970-01-01 to 2000-01-01, the base of the received date.
+const EPOCH_2000_DAYS: i64 = 10957;
+
+/// Release date of Diamond and Pearl as days since 2000-01-01, no card can be received earlier.
+pub const RELEASE_DAYS: u16 = 2462;
+
+/// A calendar date.
+/// Representation: (year, month of year, day of month)
+pub type Date = (u16, u8, u8);
+
+/// Converts days since 2000-01-01 to a [Date] of the proleptic gregorian calendar.
+pub fn from_days(days: u16) -> Date {
+    // shift the epoch to 0000-03-01, so the leap day is the last day of the year
+    let days = days as i64 + EPOCH_2000_DAYS + 719468;
+    let era = days / 146097;
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year as u16, month as u8, day as u8)
+}
+
+/// Converts a [Date] to days since 2000-01-01.
+///
+/// # Returns
+///
+/// Returns an error if the date does not exist or cannot be represented.
+pub fn to_days(date: Date) -> Result<u16, String> {
+    let (year, month, day) = date;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return Err(format!("{}-{:02}-{:02} is not a valid date", year, month, day));
+    }
+    let year = year as i64 - if month <= 2 { 1 } else { 0 };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146097 + day_of_era - 719468 - EPOCH_2000_DAYS;
+    u16::try_from(days).map_err(|_| format!("{}-{:02}-{:02} is out of range, only dates from {} to {} are possible", date.0, date.1, date.2, fmt(from_days(0)), fmt(from_days(u16::MAX))))
+}
+
+fn is_leap(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 => if is_leap(year) { 29 } else { 28 },
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The current date as days since 2000-01-01.
+pub fn today() -> Result<u16, String> {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|e| e.to_string())?.as_secs() as i64;
+    u16::try_from(secs / 86400 - EPOCH_2000_DAYS).map_err(|_| "The current date cannot be represented".to_string())
+}
+
+/// Formats a [Date] as ISO 8601 `YYYY-MM-DD`.
+pub fn fmt(date: Date) -> String {
+    format!("{}-{:02}-{:02}", date.0, date.1, date.2)
+}
+
+/// Parses a date given as `YYYY-MM-DD`, `today` or as days since 2000-01-01.
+pub fn parse(value: &str) -> Result<u16, String> {
+    if value.eq_ignore_ascii_case("today") {
+        return today();
+    }
+    if let Ok(days) = value.parse::<u16>() {
+        return Ok(days);
+    }
+    let parts: Vec<&str> = value.split('-').collect();
+    if parts.len() != 3 {
+        return Err(format!("A date requires the format YYYY-MM-DD, but provided: '{}'", value));
+    }
+    let invalid = |p: &str| format!("Invalid date component '{}' in '{}'", p, value);
+    let year = parts[0].parse::<u16>().map_err(|_| invalid(parts[0]))?;
+    let month = parts[1].parse::<u8>().map_err(|_| invalid(parts[1]))?;
+    let day = parts[2].parse::<u8>().map_err(|_| invalid(parts[2]))?;
+    to_days((year, month, day))
+}
+
+/// Describes why a received date cannot be genuine, if so.
+pub fn implausibility(days: u16) -> Option<&'static str> {
+    if days < RELEASE_DAYS {
+        Some("before the release of Diamond and Pearl")
+    } else if today().is_ok_and(|t| days > t) {
+        Some("in the future")
+    } else {
+        None
+    }
+}
+
+/// Parses a point in time given as `HH:MM[:SS]` or `YYYY-MM-DD HH:MM[:SS]` in UTC,
+/// a trailing `Z` or offset like `+02:00` as in RFC 3339 gives the time in another zone.
+///
+/// A time without date refers to its next occurrence, i.e. tomorrow if it already passed today.
+pub fn parse_time(value: &str) -> Result<SystemTime, String> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_err(|e| e.to_string())?.as_secs() as i64;
+    parse_time_at(value, now).map(|t| UNIX_EPOCH + Duration::from_secs(t))
+}
+
+/// Parses a point in time as [parse_time] relative to `now` in seconds since the unix epoch.
+fn parse_time_at(value: &str, now: i64) -> Result<u64, String> {
+    let invalid = || format!("A time requires the format HH:MM[:SS] or YYYY-MM-DD HH:MM[:SS] with an optional offset like Z or +02:00, but provided: '{}'", value);
+    let (date, time) = match value.trim().split_once([' ', 'T']) {
+        Some((d, t)) => (Some(d), t),
+        None => (None, value.trim()),
+    };
+    let (time, offset) = match time.find(['Z', 'z', '+', '-']) {
+        Some(i) => (&time[..i], parse_offset(&time[i..]).ok_or_else(invalid)?),
+        None => (time, 0),
+    };
+    let time: Vec<u8> = time.split(':').map(|p| p.parse::<u8>().map_err(|_| invalid())).collect::<Result<_, _>>()?;
+    let (hour, minute, second) = match *time.as_slice() {
+        [h, m] => (h, m, 0),
+        [h, m, s] => (h, m, s),
+        _ => return Err(invalid()),
+    };
+    if hour > 23 || minute > 59 || second > 59 {
+        return Err(invalid());
+    }
+
+    let time_of_day = hour as i64 * 3600 + minute as i64 * 60 + second as i64;
+    let time = match date {
+        Some(d) => (parse(d)? as i64 + EPOCH_2000_DAYS) * 86400 + time_of_day - offset,
+        None => {
+            let time = (now + offset).div_euclid(86400) * 86400 + time_of_day - offset;
+            if time <= now { time + 86400 } else { time }
+        }
+    };
+    u64::try_from(time).map_err(|_| invalid())
+}
+
+/// Parses a zone offset `Z` or `±HH:MM` to seconds east of UTC.
+fn parse_offset(value: &str) -> Option<i64> {
+    if value.eq_ignore_ascii_case("z") {
+        return Some(0);
+    }
+    let sign = match value.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let (hours, minutes) = value[1..].split_once(':')?;
+    let (hours, minutes) = (hours.parse::<i64>().ok()?, minutes.parse::<i64>().ok()?);
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_dates() {
+        assert_eq!((2000, 1, 1), from_days(0));
+        assert_eq!((2000, 2, 29), from_days(59));
+        assert_eq!((2000, 3, 1), from_days(60));
+        assert_eq!((2009, 8, 3), from_days(3502));
+        assert_eq!((2024, 8, 4), from_days(8982));
+        assert_eq!((2179, 6, 6), from_days(u16::MAX));
+        assert_eq!(Ok(3502), parse("2009-08-03"));
+        assert!(parse("2009-02-29").is_err());
+        assert!(parse("2179-06-07").is_err());
+        assert!(parse("1999-12-31").is_err());
+    }
+
+    #[test]
+    fn full_range_round_trip() {
+        let mut previous = from_days(0);
+        assert_eq!(Ok(0), to_days(previous));
+        for days in 1..=u16::MAX {
+            let date = from_days(days);
+            assert_eq!(Ok(days), to_days(date));
+            let (year, month, day) = previous;
+            let expected = if day < days_in_month(year, month) {
+                (year, month, day + 1)
+            } else if month < 12 {
+                (year, month + 1, 1)
+            } else {
+                (year + 1, 1, 1)
+            };
+            assert_eq!(expected, date);
+            previous = date;
+        }
+    }
+
+    #[test]
+    fn points_in_time() {
+        // 2024-08-04 12:00:00 UTC
+        let now = 1722772800;
+        assert_eq!(Ok(now as u64 + 6 * 3600), parse_time_at("18:00", now));
+        assert_eq!(Ok(now as u64 + 4 * 3600), parse_time_at("18:00+02:00", now));
+        // already passed today
+        assert_eq!(Ok(now as u64 + 23 * 3600 + 30 * 60), parse_time_at("11:30:00Z", now));
+        assert_eq!(Ok(now as u64 + 24 * 3600 + 2 * 3600), parse_time_at("2024-08-05 14:00", now));
+        assert_eq!(Ok(now as u64 + 5 * 3600), parse_time_at("2024-08-04T12:00:00-05:00", now));
+        assert!(parse_time_at("24:00", now).is_err());
+        assert!(parse_time_at("18:00+2", now).is_err());
+    }
+}
@@ -2,9 +2,12 @@
 
 use std::error::Error;
 use std::fs;
+use std::ops::Range;
 use std::path::PathBuf;
+use std::thread;
 
 use crate::MacAddress;
+use crate::error::err_reason;
 use crate::pcd::{Encrypted, PCD};
 
 
@@ -12,23 +15,102 @@ use crate::pcd::{Encrypted, PCD};
 ///
 /// This function reads an encrypted PCD file, decrypts its content using the provided
 /// checksum and Ethernet address, and then saves the decrypted data to a new file.
+/// Without checksum, the checksum is recovered by trying every possible value.
+/// The decrypted data is verified, invalid data is only written if forced.
 ///
 /// # Arguments
 ///
 /// * `epcd_file` - A [PathBuf] representing the path to the encrypted PCD file.
-/// * `checksum` - The checksum value used for decryption.
+/// * `checksum` - The checksum value used for decryption, recovered if none is given.
 /// * `address` - A [MacAddress] representing the Ethernet address.
 /// * `pcd_file` - A [PathBuf] representing the path to the output decrypted PCD file.
+/// * `force` - Write the decrypted data even if it fails the verification.
 ///
 /// # Returns
 ///
 /// Returns [Ok(())] if the decryption and file write process runs successfully,
 /// otherwise returns an error wrapped in a [Box<dyn Error>].
 ///
-pub fn decrypt(epcd_file: PathBuf, checksum: u16, address: MacAddress, pcd_file: PathBuf) -> Result<(), Box<dyn Error>> {
+pub fn decrypt(epcd_file: PathBuf, checksum: Option<u16>, address: MacAddress, pcd_file: PathBuf, force: bool) -> Result<(), Box<dyn Error>> {
     let data = fs::read(epcd_file)?;
     let pcd: PCD<Encrypted> = data.as_slice().try_into()?;
-    let decrypted_data = pcd.decrypt(&address, checksum).simplify().data();
+    let checksum = match checksum {
+        Some(c) => c,
+        None => {
+            eprintln!("No checksum given, trying all 65536 checksums...");
+            match recover_checksum(&pcd, &address)[..] {
+                [c] => {
+                    eprintln!("Recovered checksum {:04x} ({})", c, c);
+                    c
+                }
+                [] => return Err("No checksum decrypts the PCD, is the MAC address correct?".into()),
+                ref candidates => return Err(format!("The checksum is ambiguous, candidates: {}",
+                                                     candidates.iter().map(|c| format!("{:04x}", c)).collect::<Vec<String>>().join(", ")).into()),
+            }
+        }
+    };
+    let extended = pcd.decrypt(&address, checksum);
+    match extended.verify(checksum) {
+        Ok(()) => {}
+        Err(e) if force => eprintln!("Warning: the decrypted data is invalid, writing it anyway: {}", e),
+        Err(e) => return Err(err_reason("The decrypted data is invalid, check MAC address and checksum or use --force", e).into()),
+    }
+    let decrypted_data = extended.simplify().data();
     fs::write(pcd_file, decrypted_data)?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Finds the checksums which decrypt the PCD.
+///
+/// A candidate is accepted if the decrypted header equals its duplicate
+/// and the checksum of the decrypted data equals the candidate.
+/// The candidates are tried in parallel on all available cores.
+///
+/// # Arguments
+///
+/// * `pcd` - The encrypted PCD.
+/// * `address` - A [MacAddress] representing the Ethernet address of the distribution.
+///
+/// # Returns
+///
+/// Returns the matching checksums in ascending order, which is usually exactly one.
+///
+pub fn recover_checksum(pcd: &PCD<Encrypted>, address: &MacAddress) -> Vec<u16> {
+    recover_checksum_in(pcd, address, 0..u16::MAX as u32 + 1)
+}
+
+/// Finds the checksums which decrypt the PCD among the `candidates`, see [recover_checksum].
+fn recover_checksum_in(pcd: &PCD<Encrypted>, address: &MacAddress, candidates: Range<u32>) -> Vec<u16> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get()) as u32;
+    let (start, count) = (candidates.start, candidates.len() as u32);
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads).map(|t| scope.spawn(move || {
+            (start + count * t / threads..start + count * (t + 1) / threads)
+                .map(|c| c as u16)
+                .filter(|&c| pcd.headers_match(address, c) && pcd.decrypt(address, c).checksum() == Ok(c))
+                .collect::<Vec<u16>>()
+        })).collect();
+        workers.into_iter().flat_map(|w| w.join().unwrap()).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcd::{Extended, PCD_LENGTH, Partitioned, Raw};
+
+    #[test]
+    fn recover() {
+        let address = [0xa4, 0xc0, 0xe1, 0x6e, 0x76, 0x80];
+        let mut data = [0u8; PCD_LENGTH];
+        data[0] = 0x01;
+        data[0x104] = 0x42;
+        let partitioned: PCD<Partitioned> = PCD::<Raw>::try_from(data.as_slice()).unwrap().into();
+        let extended: PCD<Extended> = partitioned.into();
+        let checksum = extended.checksum().unwrap();
+        let encrypted = extended.encrypt(&address).unwrap();
+        // a part of all checksums, trying all of them takes long in debug builds
+        let start = checksum.saturating_sub(0x800) as u32;
+        assert_eq!(vec![checksum], recover_checksum_in(&encrypted, &address, start..start + 0x1000));
+    }
+}
@@ -0,0 +1,54 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::MacAddress;
+use crate::error::err_reason;
+use crate::frame::HEADER_FRAGMENT_INDEX;
+use crate::pcd::{key, Extended, Partitioned, PCD, PCD_EXTENDED_LENGTH, PCD_FRAGMENT_LENGTH, PCD_HEADER_LENGTH, PCD_PGT_LENGTH, Raw};
+
+/// Sections of the extended PCD as `(offset, length, name)`.
+const XPCD_SECTIONS: [(usize, usize, &str); 4] = [
+    (0, PCD_HEADER_LENGTH, "header"),
+    (PCD_HEADER_LENGTH, PCD_PGT_LENGTH, "pgt"),
+    (PCD_HEADER_LENGTH + PCD_PGT_LENGTH, PCD_HEADER_LENGTH, "header duplicate"),
+    (2 * PCD_HEADER_LENGTH + PCD_PGT_LENGTH, PCD_EXTENDED_LENGTH - 2 * PCD_HEADER_LENGTH - PCD_PGT_LENGTH, "card data"),
+];
+
+/// Encrypts a PCD file as it would be distributed and saves the encrypted data to a new file.
+///
+/// The checksum, the RC4 key and the layout of the fragments are printed to stdout.
+///
+/// # Arguments
+///
+/// * `pcd_file` - A [PathBuf] representing the path to the PCD file.
+/// * `address` - A [MacAddress] representing the Ethernet address of the distribution.
+/// * `epcd_file` - A [PathBuf] representing the path to the output encrypted PCD file.
+///
+/// # Returns
+///
+/// Returns [Ok(())] if the encryption and file write process runs successfully,
+/// otherwise returns an error wrapped in a [Box<dyn Error>].
+///
+pub fn encrypt(pcd_file: PathBuf, address: MacAddress, epcd_file: PathBuf) -> Result<(), Box<dyn Error>> {
+    let data = fs::read(pcd_file).map_err(|e| err_reason("Unable to read pcd file", e))?;
+    let pcd: PCD<Raw> = PCD::try_from(data.as_slice())?;
+    let partitioned: PCD<Partitioned> = pcd.into();
+    let extended: PCD<Extended> = partitioned.into();
+    let checksum = extended.checksum()?;
+    let encrypted = extended.encrypt(&address)?;
+    fs::write(&epcd_file, encrypted.data()).map_err(|e| err_reason("Unable to write encrypted pcd file", e))?;
+    eprintln!("Wrote {} bytes to '{}'", PCD_EXTENDED_LENGTH, epcd_file.display());
+    println!("checksum: {:04x}", checksum);
+    println!("key: {}", key(&address, checksum).iter().map(|b| format!("{:02x}", b)).collect::<String>());
+    println!("fragments:");
+    for i in 0..encrypted.fragments().len() {
+        let (start, end) = (i * PCD_FRAGMENT_LENGTH, (i + 1) * PCD_FRAGMENT_LENGTH);
+        let sections: Vec<&str> = XPCD_SECTIONS.iter().filter(|&&(o, l, _)| o < end && o + l > start).map(|&(_, _, n)| n).collect();
+        println!("  {:04x}: 0x{:03x}-0x{:03x} {}", i, start, end - 1, sections.join(", "));
+    }
+    println!("  {:04x}: plaintext header, zero padded to 0x{:02x} bytes", HEADER_FRAGMENT_INDEX, PCD_FRAGMENT_LENGTH);
+    Ok(())
+}
@@ -0,0 +1,258 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::date;
+use crate::error::err_reason;
+use crate::pcd;
+use crate::pcd::{CardType, Game, PCD, PCD_LENGTH, PCD_PADDING_LENGTH, PCD_PGT_LENGTH, Partitioned, Raw};
+use crate::species::species_by_pokedex;
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum Format {
+    Json,
+    Toml,
+}
+
+impl Format {
+    /// Guesses the format from the file extension, JSON is assumed for everything but `.toml`.
+    fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(e) if e.eq_ignore_ascii_case("toml") => Format::Toml,
+            _ => Format::Json,
+        }
+    }
+}
+
+/// Textual representation of a [pcd::Deserialized] for review and version control.
+///
+/// Missing fields take the value of an empty card, see [PCD::new].
+/// The round trip is lossy in one regard: bytes after the terminators of title and comment are not retained,
+/// on import the strings are padded like the games do.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Document {
+    pub title: String,
+    pub card_type: CardType,
+    pub gift_instance: u16,
+    pub card_id: u16,
+    pub games: Vec<Game>,
+    /// Bits of the games field not covered by the games
+    pub unknown_games: u16,
+    pub comment: String,
+    pub redistribution: u8,
+    /// Received date as `YYYY-MM-DD`
+    pub received: String,
+    pub slot: u8,
+    pub used: bool,
+    pub shareable: bool,
+    pub card_flags: u8,
+    pub delivery: u8,
+    /// Content of the padding regions as hexadecimal
+    pub padding: String,
+    /// The PGT as hexadecimal
+    pub pgt: String,
+    /// Exactly 3 icons
+    pub icons: Vec<Icon>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Icon {
+    pub pokedex: u16,
+    /// Name of the species, informational only and ignored on import
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub species: Option<String>,
+}
+
+impl From<u16> for Icon {
+    fn from(pokedex: u16) -> Self {
+        Icon { pokedex, species: species_by_pokedex(pokedex as usize).map(str::to_string) }
+    }
+}
+
+impl From<&pcd::Deserialized> for Document {
+    fn from(des: &pcd::Deserialized) -> Self {
+        Document {
+            title: des.title.clone(),
+            card_type: des.card_type,
+            gift_instance: des.gift_instance,
+            card_id: des.card_id,
+            games: des.games.clone(),
+            unknown_games: des.unknown_games,
+            comment: des.comment.clone(),
+            redistribution: des.redistribution,
+            received: date::fmt(date::from_days(des.received)),
+            slot: des.slot,
+            used: des.used,
+            shareable: des.shareable,
+            card_flags: des.card_flags,
+            delivery: des.delivery,
+            padding: to_hex(&des.padding),
+            pgt: to_hex(&des.pgt),
+            icons: vec![des.icons.0.into(), des.icons.1.into(), des.icons.2.into()],
+        }
+    }
+}
+
+impl Default for Document {
+    fn default() -> Self {
+        Document::from(&PCD::<pcd::Deserialized>::new().state)
+    }
+}
+
+impl TryFrom<Document> for PCD<pcd::Deserialized> {
+    type Error = String;
+
+    fn try_from(doc: Document) -> Result<Self, Self::Error> {
+        let icons: [u16; 3] = doc.icons.iter().map(|i| i.pokedex).collect::<Vec<u16>>().try_into()
+            .map_err(|i: Vec<u16>| format!("Exactly 3 icons are required, but {} were specified", i.len()))?;
+        let mut pcd = PCD::<pcd::Deserialized>::new();
+        let des = &mut pcd.state;
+        des.title = doc.title;
+        des.card_type = doc.card_type;
+        des.gift_instance = doc.gift_instance;
+        des.card_id = doc.card_id;
+        des.games = doc.games;
+        des.unknown_games = doc.unknown_games;
+        des.comment = doc.comment;
+        des.redistribution = doc.redistribution;
+        des.icons = (icons[0], icons[1], icons[2]);
+        des.received = date::parse(&doc.received)?;
+        des.slot = doc.slot;
+        des.used = doc.used;
+        des.shareable = doc.shareable;
+        des.card_flags = doc.card_flags;
+        des.delivery = doc.delivery;
+        des.padding = from_hex(&doc.padding)?.try_into().map_err(|p: Vec<u8>| format!("Padding needs to be {} bytes, but was: {}", PCD_PADDING_LENGTH, p.len()))?;
+        des.pgt = from_hex(&doc.pgt)?.try_into().map_err(|p: Vec<u8>| format!("PGT size needs to be {}, but was: {}", PCD_PGT_LENGTH, p.len()))?;
+        Ok(pcd)
+    }
+}
+
+fn to_hex(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Parses hexadecimal bytes, whitespace is ignored.
+fn from_hex(hex: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("Hexadecimal data requires an even amount of digits, but has {}", digits.len()));
+    }
+    digits.chunks_exact(2)
+        .map(|c| {
+            let byte: String = c.iter().collect();
+            u8::from_str_radix(&byte, 16).map_err(|_| format!("Invalid hexadecimal byte '{}'", byte))
+        })
+        .collect()
+}
+
+/// Exports a PCD file as text document.
+///
+/// # Arguments
+///
+/// * `pcd_file` - A [PathBuf] representing the path to the PCD file.
+/// * `format` - The format of the document.
+/// * `output` - The location of the document, printed to stdout if none is given.
+///
+/// # Returns
+///
+/// Returns [Ok(())] if the export runs successfully,
+/// otherwise returns an error wrapped in a [Box<dyn Error>].
+///
+pub fn export(pcd_file: PathBuf, format: Format, output: Option<PathBuf>) -> Result<(), Box<dyn Error>> {
+    let data = fs::read(pcd_file).map_err(|e| err_reason("Unable to read pcd file", e))?;
+    let pcd: PCD<Raw> = PCD::try_from(data.as_slice())?;
+    let doc = Document::from(&PCD::<Partitioned>::from(pcd).deserialize().state);
+    let text = match format {
+        Format::Json => serde_json::to_string_pretty(&doc)? + "\n",
+        Format::Toml => toml::to_string_pretty(&doc)?,
+    };
+    match output {
+        Some(o) => fs::write(o, text).map_err(|e| err_reason("Unable to write document", e))?,
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+/// Builds a PCD file from a text document created by [export].
+///
+/// # Arguments
+///
+/// * `document` - A [PathBuf] representing the path to the document.
+/// * `format` - The format of the document, guessed from the file extension if none is given.
+/// * `pcd_file` - A [PathBuf] representing the path to the output PCD file.
+///
+/// # Returns
+///
+/// Returns [Ok(())] if the import and file write process runs successfully,
+/// otherwise returns an error wrapped in a [Box<dyn Error>].
+///
+pub fn import(document: PathBuf, format: Option<Format>, pcd_file: PathBuf) -> Result<(), Box<dyn Error>> {
+    let text = fs::read_to_string(&document).map_err(|e| err_reason("Unable to read document", e))?;
+    let doc: Document = match format.unwrap_or_else(|| Format::from_path(&document)) {
+        Format::Json => serde_json::from_str(&text)?,
+        Format::Toml => toml::from_str(&text)?,
+    };
+    let pcd: PCD<Raw> = (&PCD::<pcd::Deserialized>::try_from(doc)?.serialize()).into();
+    let pcd_data: [u8; PCD_LENGTH] = pcd.into();
+    fs::write(pcd_file, pcd_data).map_err(|e| err_reason("Unable to write pcd file", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcd::PCD_GAMES_OFFSET;
+
+    #[test]
+    fn export_import_unknown_games() {
+        let mut pcd = PCD::<pcd::Deserialized>::new();
+        pcd.state.title = "Test Gift".to_string();
+        pcd.state.comment = "Comment".to_string();
+        pcd.state.games = vec![Game::Platinum];
+        let raw: PCD<Raw> = (&pcd.serialize()).into();
+        let mut data: [u8; PCD_LENGTH] = raw.into();
+        // game bits unknown to Game
+        data[PCD_GAMES_OFFSET] |= 0x01;
+        data[PCD_GAMES_OFFSET + 1] |= 0x02;
+
+        let dir = std::env::temp_dir();
+        let [pcd_file, doc_file, imported_file] = ["pcd", "json", "imported.pcd"].map(|e| dir.join(format!("wc-beacon-export-{}.{}", std::process::id(), e)));
+        fs::write(&pcd_file, data).unwrap();
+        export(pcd_file.clone(), Format::Json, Some(doc_file.clone())).unwrap();
+        import(doc_file.clone(), None, imported_file.clone()).unwrap();
+        let imported = fs::read(&imported_file).unwrap();
+        [pcd_file, doc_file, imported_file].iter().for_each(|f| fs::remove_file(f).unwrap());
+        assert_eq!(data.as_slice(), imported.as_slice());
+    }
+
+    #[test]
+    fn document_round_trip() {
+        let mut pcd = PCD::<pcd::Deserialized>::new();
+        pcd.state.title = "Test Gift".to_string();
+        pcd.state.card_type = CardType::Item;
+        pcd.state.gift_instance = 0x44;
+        pcd.state.games = vec![Game::Diamond, Game::HeartGold];
+        pcd.state.icons = (25, 0, 0);
+        pcd.state.received = 3502;
+        pcd.state.pgt[0x20] = 0xab;
+        let expected: PCD<Raw> = (&pcd.serialize()).into();
+
+        let toml = toml::to_string_pretty(&Document::from(&pcd.state)).unwrap();
+        assert!(toml.contains("received = \"2009-08-03\""));
+        assert!(toml.contains("species = \"Pikachu\""));
+        let json = serde_json::to_string(&toml::from_str::<Document>(&toml).unwrap()).unwrap();
+        let imported = PCD::<pcd::Deserialized>::try_from(serde_json::from_str::<Document>(&json).unwrap()).unwrap();
+        let actual: PCD<Raw> = (&imported.serialize()).into();
+
+        let expected: [u8; PCD_LENGTH] = expected.into();
+        let actual: [u8; PCD_LENGTH] = actual.into();
+        assert_eq!(expected, actual);
+    }
+}
@@ -0,0 +1,273 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+
+use crc::{Crc, CRC_32_ISO_HDLC};
+
+use crate::MacAddress;
+use crate::pcd::{PCD_EXTENDED_LENGTH, PCD_FRAGMENT_LENGTH, PCD_FRAGMENTS, PCDFragment};
+
+const CRC_32: Crc<u32> = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+
+/// Length of the frame check sequence at the end of a frame.
+pub const FCS_LENGTH: usize = 4;
+/// Fragment index of the plaintext header.
+pub const HEADER_FRAGMENT_INDEX: u16 = 0xffff;
+/// Length of the packet header within the vendor specific element.
+pub const PACKET_HEADER_LENGTH: usize = 28;
+/// Length of a packet, the packet header followed by the payload.
+pub const PACKET_LENGTH: usize = PACKET_HEADER_LENGTH + PCD_FRAGMENT_LENGTH;
+
+const BROADCAST_ADDRESS: MacAddress = [0xff; 6];
+/// Frame control field of a beacon frame, type management and subtype beacon.
+const FRAME_CONTROL: [u8; 2] = [0x80, 0x00];
+/// Length of the 802.11 header, frame control, duration, three addresses and the sequence control.
+const MAC_HEADER_LENGTH: usize = 24;
+/// Length of the fixed parameters, timestamp, beacon interval and capabilities information.
+const FIXED_PARAMETERS_LENGTH: usize = 12;
+
+const TAG_SUPPORTED_RATES: u8 = 0x01;
+const TAG_DS_PARAMETER_SET: u8 = 0x03;
+const TAG_TRAFFIC_INDICATION_MAP: u8 = 0x05;
+const TAG_VENDOR_SPECIFIC: u8 = 0xdd;
+/// 1 and 2 Mbps, both basic rates.
+const SUPPORTED_RATES: [u8; 2] = [0x82, 0x84];
+/// DTIM count, DTIM period, bitmap control and partial virtual bitmap.
+const TRAFFIC_INDICATION_MAP: [u8; 5] = [0x01, 0x02, 0x00, 0x00, 0x00];
+/// Nintendo OUI followed by the OUI type.
+const NINTENDO_OUI: [u8; 4] = [0x00, 0x09, 0xbf, 0x00];
+
+/// Errors of parsing a [NintendoBeacon].
+#[derive(Debug, PartialEq)]
+pub enum FrameError {
+    /// The frame is no beacon frame of a wondercard distribution, e.g. of any other access point or of DS Download Play.
+    NoDistribution,
+    /// The frame ends within a field.
+    Truncated,
+    /// The frame check sequence does not match the frame, it was corrupted on the air.
+    InvalidFcs { expected: u32, actual: u32 },
+}
+
+impl Display for FrameError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrameError::NoDistribution => f.write_str("no beacon frame of a wondercard distribution"),
+            FrameError::Truncated => f.write_str("the frame is truncated"),
+            FrameError::InvalidFcs { expected, actual } => write!(f, "invalid FCS {:08x}, expected {:08x}", actual, expected),
+        }
+    }
+}
+
+impl Error for FrameError {}
+
+/// The packet within the Nintendo vendor specific element, which carries one fragment of the distribution.
+///
+/// See `gen-iv-distribution-structure.md` for the layout, all values are little endian.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistributionPacket {
+    /// The amount of fragments including the header fragment.
+    pub frames_count: u32,
+    /// Values without known meaning, always `0x1, 0x1`.
+    pub unknown_1: [u16; 2],
+    /// The region, see [crate::GGID].
+    pub ggid: u32,
+    /// Values without known meaning, always `0x0, 0x70, 0x28, 0xc`.
+    pub unknown_2: [u16; 4],
+    /// The checksum of the xPCD, which is part of the encryption key.
+    pub checksum: u16,
+    /// The index of the encrypted fragment or [HEADER_FRAGMENT_INDEX] for the plaintext header.
+    pub fragment_index: u16,
+    /// The length of the encrypted xPCD.
+    pub payload_length: u32,
+    pub payload: PCDFragment,
+}
+
+impl DistributionPacket {
+    /// Creates a packet of a wondercard distribution with the values of the Pokémon Distribution Rom.
+    ///
+    /// # Arguments
+    ///
+    /// * `ggid` - The GGID of the region.
+    /// * `checksum` - The checksum of the extended PCD.
+    /// * `fragment_index` - The index of the encrypted fragment or [HEADER_FRAGMENT_INDEX].
+    /// * `payload` - The fragment.
+    ///
+    pub fn new(ggid: u32, checksum: u16, fragment_index: u16, payload: PCDFragment) -> Self {
+        DistributionPacket {
+            frames_count: PCD_FRAGMENTS as u32,
+            unknown_1: [0x1, 0x1],
+            ggid,
+            unknown_2: [0x0, 0x70, 0x28, 0xc],
+            checksum,
+            fragment_index,
+            payload_length: PCD_EXTENDED_LENGTH as u32,
+            payload,
+        }
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        [
+            self.frames_count.to_le_bytes().as_slice(),
+            &self.unknown_1.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<u8>>(),
+            &self.ggid.to_le_bytes(),
+            &self.unknown_2.iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<u8>>(),
+            &self.checksum.to_le_bytes(),
+            &self.fragment_index.to_le_bytes(),
+            &self.payload_length.to_le_bytes(),
+            &self.payload,
+        ].concat()
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, FrameError> {
+        let data: &[u8; PACKET_LENGTH] = data.get(..PACKET_LENGTH).ok_or(FrameError::Truncated)?.try_into().unwrap();
+        let u16_at = |o: usize| u16::from_le_bytes([data[o], data[o + 1]]);
+        let u32_at = |o: usize| u32::from_le_bytes([data[o], data[o + 1], data[o + 2], data[o + 3]]);
+        Ok(DistributionPacket {
+            frames_count: u32_at(0),
+            unknown_1: [u16_at(4), u16_at(6)],
+            ggid: u32_at(8),
+            unknown_2: [u16_at(12), u16_at(14), u16_at(16), u16_at(18)],
+            checksum: u16_at(20),
+            fragment_index: u16_at(22),
+            payload_length: u32_at(24),
+            payload: data[PACKET_HEADER_LENGTH..].try_into().unwrap(),
+        })
+    }
+}
+
+/// A beacon frame of a wondercard distribution without radiotap header.
+///
+/// Only the fields which differ between distributions are modeled,
+/// the supported rates and the traffic indication map are always those of the Pokémon Distribution Rom.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NintendoBeacon {
+    pub source: MacAddress,
+    pub bssid: MacAddress,
+    /// The sequence control, the sequence number shifted by 4 bits.
+    pub sequence_control: u16,
+    /// The timing synchronization function in µs.
+    pub timestamp: u64,
+    /// The beacon interval in time units of 1024 µs.
+    pub beacon_interval: u16,
+    pub capabilities: u16,
+    /// The current channel of the ds parameter set.
+    pub channel: u8,
+    pub packet: DistributionPacket,
+}
+
+impl NintendoBeacon {
+    /// Creates a beacon frame of the Pokémon Distribution Rom, which uses the address as source and bssid.
+    pub fn new(address: MacAddress, channel: u8, packet: DistributionPacket) -> Self {
+        NintendoBeacon {
+            source: address,
+            bssid: address,
+            sequence_control: 0,
+            timestamp: 0,
+            beacon_interval: 0x000a,
+            capabilities: 0x0021,
+            channel,
+            packet,
+        }
+    }
+
+    /// Serializes the beacon frame followed by the FCS.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut frame = [
+            FRAME_CONTROL.as_slice(),
+            &0x0u16.to_le_bytes(), // duration
+            &BROADCAST_ADDRESS,
+            &self.source,
+            &self.bssid,
+            &self.sequence_control.to_le_bytes(),
+            &self.timestamp.to_le_bytes(),
+            &self.beacon_interval.to_le_bytes(),
+            &self.capabilities.to_le_bytes(),
+            &[TAG_SUPPORTED_RATES, SUPPORTED_RATES.len() as u8],
+            &SUPPORTED_RATES,
+            &[TAG_DS_PARAMETER_SET, 1, self.channel],
+            &[TAG_TRAFFIC_INDICATION_MAP, TRAFFIC_INDICATION_MAP.len() as u8],
+            &TRAFFIC_INDICATION_MAP,
+            &[TAG_VENDOR_SPECIFIC, (NINTENDO_OUI.len() + PACKET_LENGTH) as u8],
+            &NINTENDO_OUI,
+            &self.packet.serialize(),
+        ].concat();
+        frame.extend_from_slice(&CRC_32.checksum(&frame).to_le_bytes());
+        frame
+    }
+
+    /// Parses a beacon frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The 802.11 frame without radiotap header.
+    /// * `fcs` - Whether the frame ends with the FCS, which is validated then.
+    ///
+    /// # Returns
+    ///
+    /// Returns the [NintendoBeacon] or the [FrameError] if the frame is no valid beacon frame of a distribution.
+    ///
+    pub fn parse(frame: &[u8], fcs: bool) -> Result<Self, FrameError> {
+        if frame.get(..2) != Some(FRAME_CONTROL.as_slice()) {
+            return Err(FrameError::NoDistribution);
+        }
+        let frame = if fcs {
+            let (frame, fcs) = frame.split_at(frame.len().checked_sub(FCS_LENGTH).ok_or(FrameError::Truncated)?);
+            let (expected, actual) = (CRC_32.checksum(frame), u32::from_le_bytes(fcs.try_into().unwrap()));
+            if expected != actual {
+                return Err(FrameError::InvalidFcs { expected, actual });
+            }
+            frame
+        } else {
+            frame
+        };
+        let fixed = frame.get(..MAC_HEADER_LENGTH + FIXED_PARAMETERS_LENGTH).ok_or(FrameError::Truncated)?;
+        let mut channel = None;
+        let mut packet = None;
+        let mut tags = &frame[MAC_HEADER_LENGTH + FIXED_PARAMETERS_LENGTH..];
+        while let [tag, length, rest @ ..] = tags {
+            let value = rest.get(..*length as usize).ok_or(FrameError::Truncated)?;
+            match *tag {
+                TAG_DS_PARAMETER_SET => channel = value.first().copied(),
+                TAG_VENDOR_SPECIFIC if value.starts_with(&NINTENDO_OUI) => packet = Some(DistributionPacket::parse(&value[NINTENDO_OUI.len()..])?),
+                _ => {}
+            }
+            tags = &rest[*length as usize..];
+        }
+        let u16_at = |o: usize| u16::from_le_bytes([fixed[o], fixed[o + 1]]);
+        Ok(NintendoBeacon {
+            source: fixed[10..16].try_into().unwrap(),
+            bssid: fixed[16..22].try_into().unwrap(),
+            sequence_control: u16_at(22),
+            timestamp: u64::from_le_bytes(fixed[24..32].try_into().unwrap()),
+            beacon_interval: u16_at(32),
+            capabilities: u16_at(34),
+            channel: channel.unwrap_or_default(),
+            packet: packet.ok_or(FrameError::NoDistribution)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn beacon_round_trip() {
+        let mut payload = [0u8; PCD_FRAGMENT_LENGTH];
+        payload[0] = 0x42;
+        let mut beacon = NintendoBeacon::new([0xa4, 0xc0, 0xe1, 0x6e, 0x76, 0x80], 11, DistributionPacket::new(0x400318, 0x1cb4, 3, payload));
+        beacon.sequence_control = 5 << 4;
+        beacon.timestamp = 51200;
+        let mut frame = beacon.serialize();
+        assert_eq!(MAC_HEADER_LENGTH + FIXED_PARAMETERS_LENGTH + 4 + 3 + 7 + 2 + NINTENDO_OUI.len() + PACKET_LENGTH + FCS_LENGTH, frame.len());
+        // the layout of the distribution rom
+        assert_eq!([0x0a, 0x00, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00, 0x18, 0x03, 0x40, 0x00, 0x00, 0x00, 0x70, 0x00, 0x28, 0x00, 0x0c, 0x00, 0xb4, 0x1c, 0x03, 0x00, 0xa8, 0x03, 0x00, 0x00, 0x42],
+                   frame[frame.len() - FCS_LENGTH - PACKET_LENGTH..][..PACKET_HEADER_LENGTH + 1]);
+        assert_eq!(Ok(beacon.clone()), NintendoBeacon::parse(&frame, true));
+        assert_eq!(Ok(beacon), NintendoBeacon::parse(&frame[..frame.len() - FCS_LENGTH], false));
+
+        frame[40] ^= 1;
+        assert!(matches!(NintendoBeacon::parse(&frame, true), Err(FrameError::InvalidFcs { .. })));
+    }
+}
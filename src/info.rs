@@ -0,0 +1,283 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::date;
+use crate::export::Icon;
+use crate::pcd::{CardType, Game, PCD, PCD_CARD_SLOT_OFFSET, PCD_CARD_USED_OFFSET, PCD_PGT_LENGTH, Partitioned, Raw, pgt_info};
+use crate::pgf;
+use crate::pgf::{PGF, PGF_LENGTH, PGF_RANDOM, PGFCardType, PGFGame};
+use crate::pgt::PGT;
+use crate::pk4;
+use crate::pk4::{NATURES, PK4, pgt_pokemon};
+use crate::species::species_by_pokedex;
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum InfoFormat {
+    /// Human-readable text
+    Text,
+    Json,
+    Yaml,
+}
+
+/// The information about a single file in the machine-readable formats.
+#[derive(Serialize)]
+pub struct FileInfo {
+    pub path: PathBuf,
+    #[serde(flatten)]
+    pub card: Card,
+}
+
+/// The card of a file, tagged with the file format it was read from.
+#[derive(Serialize)]
+#[serde(tag = "format", rename_all = "lowercase")]
+pub enum Card {
+    Pcd(PcdCard),
+    Pgt(PgtCard),
+    Pgf(PgfCard),
+}
+
+#[derive(Serialize)]
+pub struct PcdCard {
+    pub title: String,
+    pub card_type: CardType,
+    pub gift_instance: u16,
+    pub card_id: u16,
+    pub games: Vec<Game>,
+    pub comment: String,
+    pub redistribution: u8,
+    /// Received date as `YYYY-MM-DD`
+    pub received: String,
+    pub slot: u8,
+    pub used: bool,
+    pub shareable: bool,
+    pub icons: Vec<Icon>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pokemon: Option<Pokemon>,
+}
+
+#[derive(Serialize)]
+pub struct PgtCard {
+    pub card_type: CardType,
+    pub gift_instance: u16,
+    pub slot: u8,
+    pub used: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pokemon: Option<Pokemon>,
+}
+
+#[derive(Serialize)]
+pub struct PgfCard {
+    pub title: String,
+    /// [None] for card types unknown to [PGFCardType]
+    pub card_type: Option<PGFCardType>,
+    pub card_id: u16,
+    /// An empty list means no restriction
+    pub games: Vec<PGFGame>,
+    /// Received date as `YYYY-MM-DD`
+    pub received: String,
+    /// Item or entree power id, depending on the card type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gift_id: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pokemon: Option<Pokemon>,
+}
+
+/// The Pokémon of a PGT or PGF, values the game chooses randomly are [None].
+#[derive(Serialize)]
+pub struct Pokemon {
+    pub species: u16,
+    pub species_name: Option<String>,
+    pub level: u8,
+    pub nickname: String,
+    pub ot_name: String,
+    pub ot_id: u16,
+    pub ot_secret_id: u16,
+    pub pid: u32,
+    pub nature: Option<String>,
+    pub held_item: u16,
+    pub ball: u8,
+    pub language: u8,
+    pub moves: [u16; 4],
+    /// Order: HP, Attack, Defense, Speed, Special Attack, Special Defense
+    pub ivs: Vec<Option<u8>>,
+    pub is_egg: bool,
+}
+
+impl From<&PK4<pk4::Deserialized>> for Pokemon {
+    fn from(pk: &PK4<pk4::Deserialized>) -> Self {
+        let des = &pk.state;
+        Pokemon {
+            species: des.species,
+            species_name: species_by_pokedex(des.species as usize).map(str::to_string),
+            level: des.level,
+            nickname: des.nickname.clone(),
+            ot_name: des.ot_name.clone(),
+            ot_id: des.ot_id,
+            ot_secret_id: des.ot_secret_id,
+            pid: des.pid,
+            nature: Some(NATURES[pk.nature() as usize].to_string()),
+            held_item: des.held_item,
+            ball: des.ball,
+            language: des.language,
+            moves: des.moves,
+            ivs: des.ivs.iter().map(|&iv| Some(iv)).collect(),
+            is_egg: des.is_egg,
+        }
+    }
+}
+
+impl From<&pgf::PGFPokemon> for Pokemon {
+    fn from(pk: &pgf::PGFPokemon) -> Self {
+        let random = |v: u8| if v == PGF_RANDOM { None } else { Some(v) };
+        Pokemon {
+            species: pk.species,
+            species_name: species_by_pokedex(pk.species as usize).map(str::to_string),
+            level: pk.level,
+            nickname: pk.nickname.clone(),
+            ot_name: pk.ot_name.clone(),
+            ot_id: pk.ot_id,
+            ot_secret_id: pk.ot_secret_id,
+            pid: pk.pid,
+            nature: random(pk.nature).map(|n| NATURES.get(n as usize).unwrap_or(&"Unknown").to_string()),
+            held_item: pk.held_item,
+            ball: pk.ball,
+            language: pk.language,
+            moves: pk.moves,
+            ivs: pk.ivs.iter().map(|&iv| random(iv)).collect(),
+            is_egg: pk.is_egg,
+        }
+    }
+}
+
+/// Reads a PCD, a bare PGT or a PGF, which are told apart by the size of the `data`.
+///
+/// # Returns
+///
+/// Returns the human-readable text and the [Card] for the machine-readable formats.
+///
+fn read_card(data: &[u8]) -> Result<(String, Card), Box<dyn Error>> {
+    if data.len() == PGF_LENGTH {
+        let pgf = PGF::<pgf::Raw>::try_from(data)?.deserialize();
+        let des = &pgf.state;
+        let card = PgfCard {
+            title: des.title.clone(),
+            card_type: des.card_type,
+            card_id: des.card_id,
+            games: des.games.clone(),
+            received: date::fmt(des.received),
+            gift_id: des.card_type.filter(|&t| t != PGFCardType::Pokemon).map(|_| des.gift_id),
+            pokemon: (des.card_type == Some(PGFCardType::Pokemon)).then(|| Pokemon::from(&des.pokemon)),
+        };
+        return Ok((format!("{}\n", pgf), Card::Pgf(card)));
+    }
+    let (mut text, pgt, mut card) = if data.len() == PCD_PGT_LENGTH {
+        let pgt = PGT::try_from(data)?;
+        let (card_type, gift_instance) = pgt_info(&pgt.data);
+        let card = PgtCard { card_type, gift_instance, slot: pgt.data[PCD_CARD_SLOT_OFFSET], used: pgt.data[PCD_CARD_USED_OFFSET] > 0, pokemon: None };
+        (format!("{}\n", pgt), pgt, Card::Pgt(card))
+    } else {
+        let pcd: PCD<Raw> = PCD::try_from(data)?;
+        let partitioned: PCD<Partitioned> = pcd.into();
+        let des = partitioned.deserialize();
+        let card = PcdCard {
+            title: des.state.title.clone(),
+            card_type: des.state.card_type,
+            gift_instance: des.state.gift_instance,
+            card_id: des.state.card_id,
+            games: des.state.games.clone(),
+            comment: des.state.comment.clone(),
+            redistribution: des.state.redistribution,
+            received: date::fmt(des.received()),
+            slot: des.state.slot,
+            used: des.state.used,
+            shareable: des.state.shareable,
+            icons: vec![des.state.icons.0.into(), des.state.icons.1.into(), des.state.icons.2.into()],
+            pokemon: None,
+        };
+        (format!("{}\n", des), PGT::from(&des), Card::Pcd(card))
+    };
+    if let Some(pk) = pgt_pokemon(&pgt.data, pgt_info(&pgt.data).0) {
+        let pk = pk.decrypt().deserialize();
+        text += &format!("{}\n", pk);
+        match &mut card {
+            Card::Pcd(c) => c.pokemon = Some(Pokemon::from(&pk)),
+            Card::Pgt(c) => c.pokemon = Some(Pokemon::from(&pk)),
+            Card::Pgf(_) => {}
+        }
+    }
+    Ok((text, card))
+}
+
+/// Shows information about PCD, bare PGT or PGF files, which are told apart by the file size.
+///
+/// The information is printed to stdout, in the machine-readable formats as a single object
+/// for one file and as an array for multiple files.
+///
+/// # Arguments
+///
+/// * `files` - The paths of the PCD, PGT or PGF files.
+/// * `format` - The [InfoFormat] of the output.
+///
+/// # Returns
+///
+/// Returns [Ok(())] if all files could be read,
+/// otherwise returns an error wrapped in a [Box<dyn Error>].
+///
+pub fn info(files: Vec<PathBuf>, format: InfoFormat) -> Result<(), Box<dyn Error>> {
+    let mut texts = Vec::new();
+    let mut infos = Vec::new();
+    for path in files {
+        let data = fs::read(&path).map_err(|e| format!("Unable to read '{}': {}", path.display(), e))?;
+        let (text, card) = read_card(&data).map_err(|e| format!("Unable to read '{}': {}", path.display(), e))?;
+        texts.push((path.clone(), text));
+        infos.push(FileInfo { path, card });
+    }
+    match format {
+        InfoFormat::Text if texts.len() == 1 => print!("{}", texts[0].1),
+        InfoFormat::Text => {
+            let sections: Vec<String> = texts.iter().map(|(p, t)| format!("==> {} <==\n{}", p.display(), t)).collect();
+            print!("{}", sections.join("\n"));
+        }
+        InfoFormat::Json if infos.len() == 1 => println!("{}", serde_json::to_string_pretty(&infos[0])?),
+        InfoFormat::Json => println!("{}", serde_json::to_string_pretty(&infos)?),
+        InfoFormat::Yaml if infos.len() == 1 => print!("{}", serde_yaml_ng::to_string(&infos[0])?),
+        InfoFormat::Yaml => print!("{}", serde_yaml_ng::to_string(&infos)?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcd::{Deserialized, PCD_LENGTH};
+
+    #[test]
+    fn machine_readable() {
+        let mut pcd = PCD::<Deserialized>::new();
+        pcd.state.title = "Test Gift".to_string();
+        pcd.state.card_type = CardType::Item;
+        pcd.state.icons = (25, 0, 0);
+        pcd.state.received = 3502;
+        let raw: PCD<Raw> = (&pcd.serialize()).into();
+        let data: [u8; PCD_LENGTH] = raw.into();
+
+        let (text, card) = read_card(&data).unwrap();
+        assert!(text.starts_with("title: Test Gift"));
+        let info = FileInfo { path: PathBuf::from("test.pcd"), card };
+        let json: serde_json::Value = serde_json::to_value(&info).unwrap();
+        assert_eq!("pcd", json["format"]);
+        assert_eq!("test.pcd", json["path"]);
+        assert_eq!("Test Gift", json["title"]);
+        assert_eq!("2009-08-03", json["received"]);
+        assert_eq!("Pikachu", json["icons"][0]["species"]);
+        assert!(json.get("pokemon").is_none());
+        let yaml = serde_yaml_ng::to_string(&info).unwrap();
+        assert!(yaml.contains("title: Test Gift"));
+    }
+}
@@ -4,22 +4,49 @@ extern crate core;
 
 use std::fmt::{Display, Formatter};
 use std::path::PathBuf;
+use std::time::SystemTime;
 use std::result::Result;
 
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
 use crate::beacon::distribute;
 use crate::decrypt::decrypt;
-use crate::crud::{info, set};
+use crate::encrypt::encrypt;
+use crate::convert::{pcd_to_pgt, pgt_to_pcd};
+use crate::crud::set;
+use crate::export::{export, import, Format};
+use crate::info::{info, InfoFormat};
+use crate::catalog::{catalog, CatalogFormat};
 use crate::pcd::{CardType, Game};
+use crate::pgf::PGFGame;
+use crate::pk4::NATURES;
+use crate::radiotap::Radiotap;
+use crate::sink::open_sink;
+use crate::sniff::{extract, sniff};
 
 mod pcd;
 mod beacon;
 mod decrypt;
+mod encrypt;
 mod pokestr;
 mod crud;
 mod error;
 mod species;
+mod pk4;
+mod date;
+mod pgt;
+mod convert;
+mod pgf;
+mod export;
+mod radiotap;
+mod playlist;
+mod session;
+mod pacing;
+mod sniff;
+mod frame;
+mod sink;
+mod info;
+mod catalog;
 
 pub mod pokestrmap {
     include!(concat!(env!("OUT_DIR"), "/pokestrmap.rs"));
@@ -36,11 +63,21 @@ pub mod speciesmap {
 fn main() {
     let cli = Cli::parse();
     let res = match cli.command {
-        Command::Distribute { pcd, region, device, address, interval } =>
-            distribute(pcd, region, device, address.unwrap_or([0xa4, 0xc0, 0xe1, 0x6e, 0x76, 0x80]), interval),
-        Command::Decrypt { epcd, checksum, address, pcd } => decrypt(epcd, checksum, address, pcd),
-        Command::Info { pcd } => info(pcd),
-        Command::Set { title, kind: card_type, gift_instance, card_id, games, description: comment, redistribution, icons, pgt, date: received, pcd, output } => set(title, card_type, card_id, gift_instance, games, comment, redistribution, icons, pgt, received, pcd, output)
+        Command::Distribute { cards, region, device, address, pacing, radio, pcap_out, hex, limits } => {
+            let address = address.unwrap_or([0xa4, 0xc0, 0xe1, 0x6e, 0x76, 0x80]);
+            open_sink(device, address, pcap_out, hex).and_then(|sink| distribute(cards, region.into_iter().flat_map(Regions::ggids).collect(), sink, address, pacing, radio, limits))
+        }
+        Command::Decrypt { epcd, checksum, address, pcd, force } => decrypt(epcd, checksum, address, pcd, force),
+        Command::Encrypt { pcd, address, epcd } => encrypt(pcd, address, epcd),
+        Command::Sniff { device, region, pcd } => sniff(device, region, pcd),
+        Command::Extract { capture, output } => extract(capture, output),
+        Command::Info { pcd, format } => info(pcd, format),
+        Command::Convert { conversion: Conversion::PcdToPgt { pcd, pgt } } => pcd_to_pgt(pcd, pgt),
+        Command::Convert { conversion: Conversion::PgtToPcd { pgt, card_id, pcd } } => pgt_to_pcd(pgt, card_id, pcd),
+        Command::Catalog { directory, format, output } => catalog(directory, format, output),
+        Command::Export { pcd, format, output } => export(pcd, format, output),
+        Command::Import { document, format, pcd } => import(document, format, pcd),
+        Command::Set { fields, card, pokemon, pcd, output } => set(fields, card, pokemon, pcd, output)
     };
     if let Err(err) = res {
         eprintln!("Error: {}", err);
@@ -59,21 +96,29 @@ enum Command {
     /// Distribute a given PCD file to the Pokemon games using a WiFi device
     #[command(name = "dist")]
     Distribute {
-        /// The PCD file to distribute
-        #[arg(short, long, value_name = "PCD_FILE")]
-        pcd: PathBuf,
-        /// The region to distribute the wondercard in
-        #[arg(short, long, value_enum)]
-        region: GGID,
+        #[command(flatten)]
+        cards: PlaylistArgs,
+        /// The region to distribute in: ja, en, fr, de, it, es, ko or all, use multiple times to distribute in several regions at once
+        #[arg(short, long, value_name = "REGION", value_parser = regions_parser)]
+        region: Vec<Regions>,
         /// The WiFi device to use for the distribution
-        #[arg(short, long)]
-        device: String,
+        #[arg(short, long, required_unless_present_any = ["pcap_out", "hex"])]
+        device: Option<String>,
         /// MAC Address to spoof [default: a4:c0:e1:6e:76:80]
         #[arg(short, long, value_parser = mac_address_parser)]
         address: Option<MacAddress>,
-        /// The interval used for the beacon frames in µs
-        #[arg(short, long, default_value_t = 10240)]
-        interval: u64,
+        #[command(flatten)]
+        pacing: PacingArgs,
+        #[command(flatten)]
+        radio: RadioArgs,
+        /// Write the frames to a pcap savefile instead of sending them, no device or privileges required
+        #[arg(long, value_name = "PCAP_FILE")]
+        pcap_out: Option<PathBuf>,
+        /// Print the frames as hexadecimal with their offset in µs to stdout instead of sending them, for dry runs
+        #[arg(long, conflicts_with = "pcap_out")]
+        hex: bool,
+        #[command(flatten)]
+        limits: LimitArgs,
     },
     /// Decrypt a PCD file which was distributed over the network
     #[command(name = "dec")]
@@ -81,69 +126,337 @@ enum Command {
         /// The encrypted PCD file to decrypt
         #[arg(short, long, value_name = "ENCRYPTED_PCD_FILE")]
         epcd: PathBuf,
-        /// The original checksum of the PCD
+        /// The original checksum of the PCD, recovered by trying every checksum if omitted
         #[arg(short, long)]
-        checksum: u16,
+        checksum: Option<u16>,
         /// The MAC Address used for the original distribution
         #[arg(short, long, value_parser = mac_address_parser)]
         address: MacAddress,
         /// The location of the decrypted output, will be overwritten if the file already exists
         #[arg(short, long, value_name = "PCD_FILE")]
         pcd: PathBuf,
+        /// Write the decrypted data even if it fails the verification, e.g. for unusual cards
+        #[arg(long)]
+        force: bool,
+    },
+    /// Encrypt a PCD file as it would be distributed and print the checksum, key and fragments
+    #[command(name = "enc")]
+    Encrypt {
+        /// The PCD file to encrypt
+        #[arg(short, long, value_name = "PCD_FILE")]
+        pcd: PathBuf,
+        /// The MAC Address of the distribution
+        #[arg(short, long, value_parser = mac_address_parser)]
+        address: MacAddress,
+        /// The location of the encrypted output, will be overwritten if the file already exists
+        #[arg(short = 'o', long, value_name = "ENCRYPTED_PCD_FILE")]
+        epcd: PathBuf,
+    },
+    /// Capture a distribution on a WiFi device in monitor mode and save the decrypted PCD file
+    #[command(name = "sniff")]
+    Sniff {
+        /// The WiFi device to capture on, tuned to the channel of the distribution
+        #[arg(short, long)]
+        device: String,
+        /// Only capture the distribution of this region: ja, en, fr, de, it, es or ko
+        #[arg(short, long, value_name = "REGION")]
+        region: Option<GGID>,
+        /// The location of the decrypted output, will be overwritten if the file already exists
+        #[arg(short = 'o', long, value_name = "PCD_FILE")]
+        pcd: PathBuf,
+    },
+    /// Extract every distributed card of a recorded pcap or pcapng capture as decrypted PCD files
+    #[command(name = "extract")]
+    Extract {
+        /// The capture with the beacon frames of the distributions
+        #[arg(value_name = "CAPTURE_FILE")]
+        capture: PathBuf,
+        /// The directory to write the PCD files to, named by region, MAC address and checksum
+        #[arg(short, long, value_name = "DIRECTORY")]
+        output: PathBuf,
     },
-    /// Show information about a given pcd file
+    /// Show information about given pcd, pgt or pgf files
     #[command(name = "info")]
     Info {
-        /// The PCD file to show the information about
+        /// The PCD, PGT or PGF files to show the information about
+        #[arg(short, long, value_name = "PCD_FILE", num_args = 1.., required = true)]
+        pcd: Vec<PathBuf>,
+        /// The format of the information, JSON and YAML list multiple files as array
+        #[arg(short, long, value_enum, default_value_t = InfoFormat::Text)]
+        format: InfoFormat,
+    },
+    /// Build an index of all PCD files in a directory tree
+    #[command(name = "catalog")]
+    Catalog {
+        /// The directory to search for .pcd and .wc4 files
+        #[arg(value_name = "DIRECTORY")]
+        directory: PathBuf,
+        /// The format of the index
+        #[arg(short, long, value_enum, default_value_t = CatalogFormat::Csv)]
+        format: CatalogFormat,
+        /// The location of the index, will be overwritten if the file already exists, printed if omitted
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Convert between PCD and PGT files
+    #[command(name = "convert")]
+    Convert {
+        #[command(subcommand)]
+        conversion: Conversion,
+    },
+    /// Export a PCD file as JSON or TOML document
+    #[command(name = "export")]
+    Export {
+        /// The PCD file to export
         #[arg(short, long, value_name = "PCD_FILE")]
-        pcd: PathBuf
+        pcd: PathBuf,
+        /// The format of the document
+        #[arg(short, long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        /// The location of the document, will be overwritten if the file already exists, printed if omitted
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Create a PCD file from a JSON or TOML document
+    #[command(name = "import")]
+    Import {
+        /// The document to import
+        #[arg(value_name = "FILE")]
+        document: PathBuf,
+        /// The format of the document, guessed from the file extension if omitted
+        #[arg(short, long, value_enum)]
+        format: Option<Format>,
+        /// The location of the PCD output, will be overwritten if the file already exists
+        #[arg(short = 'o', long, value_name = "PCD_FILE")]
+        pcd: PathBuf,
     },
-    /// Create a new PCD file or edit an existing one
+    /// Create a new PCD file or edit an existing PCD or PGF file
     #[command(name = "set")]
     Set {
-        /// PCD file to edit, non-destructive only for input, leave empty to create from scratch, kind and gift instance id have precedence over pcd data
+        /// PCD or PGF file to edit, non-destructive only for input, leave empty to create a PCD from scratch, kind and gift instance id have precedence over pcd data
         #[arg(short, long, value_name = "PCD_FILE")]
         pcd: Option<PathBuf>,
-        /// Wonder Card title
-        #[arg(short, long, value_name = "TITLE")]
-        title: Option<String>,
-        /// Wonder Card Type
-        #[arg(short, long, value_name = "KIND")]
-        kind: Option<CardType>,
-        /// Wonder Card ID
-        #[arg(short, long, value_name = "ID")]
-        card_id: Option<u16>,
-        /// Gift instance, use id from item, pokewalker area, poketch app, rule, seal, accessory or unknown
-        #[arg(long, value_name = "GIFT INSTANCE ID")]
-        gift_instance: Option<u16>,
-        /// Games to distribute to
-        #[arg(short, long, value_name = "GAMES")]
-        games: Option<Vec<Game>>,
-        /// Wonder Card comment/description
-        #[arg(short, long, value_name = "DESCRIPTION")]
-        description: Option<String>,
-        /// How often players can redistribute, 255 for unlimited
-        #[arg(short, long, value_name = "REDISTRIBUTION")]
-        redistribution: Option<u8>,
-        /// Exactly 3 Wonder Card Icons (use arg 3 times), use Pokédex index and 0 for none
-        #[arg(short, long, value_name = "ICONS")]
-        icons: Option<Vec<u16>>,
-        /// PGT File
-        #[arg(long, value_name = "PGT")]
-        pgt: Option<PathBuf>,
-        /// Wonder Card received date
-        #[arg(long, value_name = "received")]
-        date: Option<u16>,
+        #[command(flatten)]
+        fields: WondercardArgs,
+        #[command(flatten)]
+        card: CardArgs,
+        #[command(flatten)]
+        pokemon: PokemonArgs,
         /// Output
         #[arg(short, long, value_name = "FILE")]
         output: PathBuf,
     },
 }
 
+#[derive(Subcommand)]
+enum Conversion {
+    /// Extract the PGT from a PCD file
+    #[command(name = "pcd2pgt")]
+    PcdToPgt {
+        /// The PCD file to extract the PGT from
+        #[arg(short, long, value_name = "PCD_FILE")]
+        pcd: PathBuf,
+        /// The location of the PGT output, will be overwritten if the file already exists
+        #[arg(short = 'o', long, value_name = "PGT_FILE")]
+        pgt: PathBuf,
+    },
+    /// Wrap a PGT file into a PCD with generated title, description and icons
+    #[command(name = "pgt2pcd")]
+    PgtToPcd {
+        /// The PGT file to wrap
+        #[arg(short = 'g', long, value_name = "PGT_FILE")]
+        pgt: PathBuf,
+        /// Wonder Card ID, defaults to the gift instance
+        #[arg(short, long, value_name = "ID")]
+        card_id: Option<u16>,
+        /// The location of the PCD output, will be overwritten if the file already exists
+        #[arg(short = 'o', long, value_name = "PCD_FILE")]
+        pcd: PathBuf,
+    },
+}
+
+/// The cards of the distribution and when to switch between them.
+#[derive(Args)]
+pub struct PlaylistArgs {
+    /// The PCD file to distribute, use multiple times to switch between the cards
+    #[arg(short, long, value_name = "PCD_FILE", required_unless_present = "playlist")]
+    pub pcd: Vec<PathBuf>,
+    /// A playlist file with a PCD file and optionally its region per line, played after the PCD files
+    #[arg(long, value_name = "PLAYLIST_FILE")]
+    pub playlist: Option<PathBuf>,
+    /// Seconds to distribute a card before switching to the next one, the current round is completed
+    #[arg(long, value_name = "SECONDS", default_value_t = 60)]
+    pub dwell: u64,
+    /// Complete rounds to distribute a card before switching to the next one, instead of the dwell time
+    #[arg(long, value_name = "ROUNDS", conflicts_with = "dwell")]
+    pub dwell_rounds: Option<u64>,
+}
+
+/// Limits ending the distribution, whichever is reached first.
+#[derive(Args)]
+pub struct LimitArgs {
+    /// Seconds to distribute
+    #[arg(long, value_name = "SECONDS")]
+    pub duration: Option<u64>,
+    /// Complete rounds of every region to distribute, 1 for a pcap savefile if no limit is given
+    #[arg(long, value_name = "ROUNDS")]
+    pub rounds: Option<u64>,
+    /// UTC time to stop distributing at as HH:MM[:SS] or YYYY-MM-DD HH:MM[:SS], append an offset like +02:00 for local time
+    #[arg(long, value_name = "TIME", value_parser = date::parse_time)]
+    pub until: Option<SystemTime>,
+}
+
+/// Timing of the beacon frames.
+#[derive(Args)]
+pub struct PacingArgs {
+    /// The interval used for the beacon frames in µs
+    #[arg(short, long, default_value_t = 10240)]
+    pub interval: u64,
+    /// Busy-wait instead of sleeping for the last µs before each frame, more precise at the cost of CPU time
+    #[arg(long, value_name = "MICROSECONDS", default_value_t = 0)]
+    pub busy_wait: u64,
+}
+
+/// Radio settings of the distribution.
+#[derive(Args)]
+pub struct RadioArgs {
+    /// The Wi-Fi channel to distribute on, the device has to be tuned to the same channel
+    #[arg(short, long, default_value_t = 7, value_parser = clap::value_parser!(u8).range(1..=14))]
+    pub channel: u8,
+    /// The radiotap header preceding the frames, try minimal if the device rejects the frames
+    #[arg(long, value_enum, default_value_t = Radiotap::Legacy)]
+    pub radiotap: Radiotap,
+    /// The transmit power in dBm, minimal radiotap header only
+    #[arg(long, value_name = "DBM", allow_negative_numbers = true)]
+    pub tx_power: Option<i8>,
+}
+
+/// Edits of the main card fields, PGF files support title, card id, gen5 games and date only.
+#[derive(Args)]
+pub struct WondercardArgs {
+    /// Wonder Card title
+    #[arg(short, long, value_name = "TITLE")]
+    pub title: Option<String>,
+    /// Wonder Card Type
+    #[arg(short, long, value_name = "KIND")]
+    pub kind: Option<CardType>,
+    /// Wonder Card ID
+    #[arg(short, long, value_name = "ID")]
+    pub card_id: Option<u16>,
+    /// Gift instance, use id from item, pokewalker area, poketch app, rule, seal, accessory or unknown
+    #[arg(long, value_name = "GIFT INSTANCE ID")]
+    pub gift_instance: Option<u16>,
+    /// Games to distribute to
+    #[arg(short, long, value_name = "GAMES")]
+    pub games: Option<Vec<Game>>,
+    /// Generation V games to distribute to, PGF only, leave empty for all
+    #[arg(long, value_name = "GAMES", num_args = 0..)]
+    pub gen5_games: Option<Vec<PGFGame>>,
+    /// Wonder Card comment/description
+    #[arg(short, long, value_name = "DESCRIPTION")]
+    pub description: Option<String>,
+    /// How often players can redistribute, 255 for unlimited
+    #[arg(short, long, value_name = "REDISTRIBUTION")]
+    pub redistribution: Option<u8>,
+    /// Exactly 3 Wonder Card Icons (use arg 3 times), use Pokédex index and 0 for none
+    #[arg(short, long, value_name = "ICONS")]
+    pub icons: Option<Vec<u16>>,
+    /// PGT File
+    #[arg(long, value_name = "PGT")]
+    pub pgt: Option<PathBuf>,
+    /// Wonder Card received date as YYYY-MM-DD, today or days since 2000-01-01
+    #[arg(long, value_name = "DATE", value_parser = date::parse)]
+    pub date: Option<u16>,
+}
+
+impl WondercardArgs {
+    /// Whether a field is set which only exists in PCD files.
+    pub fn has_pcd_only(&self) -> bool {
+        self.kind.is_some() || self.gift_instance.is_some() || self.games.is_some() || self.description.is_some() || self.redistribution.is_some() || self.icons.is_some() || self.pgt.is_some()
+    }
+}
+
+/// Edits of the less common card fields.
+#[derive(Args)]
+pub struct CardArgs {
+    /// Slot of the card in the card album
+    #[arg(long, value_name = "SLOT")]
+    pub slot: Option<u8>,
+    /// Whether the gift was already picked up
+    #[arg(long, value_name = "USED")]
+    pub used: Option<bool>,
+    /// Whether the card can be shared with other players
+    #[arg(long, value_name = "SHAREABLE")]
+    pub shareable: Option<bool>,
+    /// Remaining card flags besides shareable as hexadecimal
+    #[arg(long, value_name = "FLAGS", value_parser = hex_u8_parser)]
+    pub card_flags: Option<u8>,
+    /// Delivery byte as hexadecimal
+    #[arg(long, value_name = "DELIVERY", value_parser = hex_u8_parser)]
+    pub delivery: Option<u8>,
+    /// Zero all regions without known meaning
+    #[arg(long)]
+    pub clear_padding: bool,
+}
+
+impl CardArgs {
+    pub fn is_empty(&self) -> bool {
+        self.slot.is_none() && self.used.is_none() && self.shareable.is_none() && self.card_flags.is_none() && self.delivery.is_none() && !self.clear_padding
+    }
+}
+
+/// Edits of the Pokémon embedded in Pokémon and egg gifts.
+#[derive(Args)]
+pub struct PokemonArgs {
+    /// Species of the Pokémon, use Pokédex index
+    #[arg(long = "pk-species", value_name = "POKEDEX_INDEX")]
+    pub species: Option<u16>,
+    /// Nickname of the Pokémon
+    #[arg(long = "pk-nickname", value_name = "NICKNAME")]
+    pub nickname: Option<String>,
+    /// Name of the original trainer
+    #[arg(long = "pk-ot-name", value_name = "NAME")]
+    pub ot_name: Option<String>,
+    /// Trainer ID of the original trainer
+    #[arg(long = "pk-ot-id", value_name = "ID")]
+    pub ot_id: Option<u16>,
+    /// Level the Pokémon is received with
+    #[arg(long = "pk-level", value_name = "LEVEL", value_parser = clap::value_parser!(u8).range(1..=100))]
+    pub level: Option<u8>,
+    /// Up to 4 moves (use arg up to 4 times), use move index
+    #[arg(long = "pk-moves", value_name = "MOVE")]
+    pub moves: Option<Vec<u16>>,
+    /// Held item, use item index and 0 for none
+    #[arg(long = "pk-item", value_name = "ITEM")]
+    pub held_item: Option<u16>,
+    /// Ball the Pokémon is caught in, e.g. 4 for Poké Ball or 16 for Cherish Ball
+    #[arg(long = "pk-ball", value_name = "BALL")]
+    pub ball: Option<u8>,
+    /// Language of origin, 1: ja, 2: en, 3: fr, 4: it, 5: de, 7: es, 8: ko
+    #[arg(long = "pk-language", value_name = "LANGUAGE")]
+    pub language: Option<u8>,
+    /// Nature by name, adjusts the PID which may change gender, ability and shininess as well
+    #[arg(long = "pk-nature", value_name = "NATURE", value_parser = nature_parser)]
+    pub nature: Option<u8>,
+    /// Exactly 6 IVs (use arg 6 times) in order HP, Attack, Defense, Speed, Special Attack, Special Defense
+    #[arg(long = "pk-ivs", value_name = "IV", value_parser = clap::value_parser!(u8).range(0..=31))]
+    pub ivs: Option<Vec<u8>>,
+    /// Exactly 3 hexadecimal ribbon bitfields (use arg 3 times) in order Sinnoh set 1, Hoenn, Sinnoh set 2
+    #[arg(long = "pk-ribbons", value_name = "RIBBONS", value_parser = hex_u32_parser)]
+    pub ribbons: Option<Vec<u32>>,
+}
+
+impl PokemonArgs {
+    pub fn is_empty(&self) -> bool {
+        self.species.is_none() && self.nickname.is_none() && self.ot_name.is_none() && self.ot_id.is_none() && self.level.is_none() && self.moves.is_none()
+            && self.held_item.is_none() && self.ball.is_none() && self.language.is_none() && self.nature.is_none() && self.ivs.is_none() && self.ribbons.is_none()
+    }
+}
+
 /// Region codes.
 /// Represent languages not regions themself, e.g. English is for UK and US.
 #[repr(u32)]
-#[derive(Copy, Clone, ValueEnum)]
+#[derive(Copy, Clone, PartialEq, ValueEnum)]
 pub enum GGID {
     /// Japanese
     #[value(name = "ja")]
@@ -183,9 +496,40 @@ impl Display for GGID {
     }
 }
 
+impl TryFrom<u32> for GGID {
+    type Error = ();
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        GGID::value_variants().iter().find(|&&g| g as u32 == value).copied().ok_or(())
+    }
+}
+
 /// A simple MAC Address representation.
 type MacAddress = [u8; 6];
 
+/// A single region or all regions.
+#[derive(Copy, Clone)]
+pub enum Regions {
+    Single(GGID),
+    All,
+}
+
+impl Regions {
+    pub fn ggids(self) -> Vec<GGID> {
+        match self {
+            Regions::Single(ggid) => vec![ggid],
+            Regions::All => GGID::value_variants().to_vec(),
+        }
+    }
+}
+
+fn regions_parser(value: &str) -> Result<Regions, String> {
+    if value.eq_ignore_ascii_case("all") {
+        return Ok(Regions::All);
+    }
+    GGID::from_str(value, true).map(Regions::Single)
+}
+
 /// Parses a MAC address string and returns a [MacAddress].
 ///
 /// A MAC address is a unique identifier assigned to network interfaces.
@@ -228,3 +572,18 @@ fn mac_address_parser(value: &str) -> Result<MacAddress, String> {
     }
     Ok(address)
 }
+
+/// Parses the name of a nature case-insensitively and returns its index.
+fn nature_parser(value: &str) -> Result<u8, String> {
+    NATURES.iter().position(|n| n.eq_ignore_ascii_case(value)).map(|n| n as u8).ok_or_else(|| format!("Unknown nature '{}', possible values: {}", value, NATURES.join(", ")))
+}
+
+/// Parses a hexadecimal [u8] with an optional `0x` prefix.
+fn hex_u8_parser(value: &str) -> Result<u8, String> {
+    u8::from_str_radix(value.trim_start_matches("0x"), 16).map_err(|e| format!("Invalid hexadecimal number '{}': {}", value, e))
+}
+
+/// Parses a hexadecimal [u32] with an optional `0x` prefix.
+fn hex_u32_parser(value: &str) -> Result<u32, String> {
+    u32::from_str_radix(value.trim_start_matches("0x"), 16).map_err(|e| format!("Invalid hexadecimal number '{}': {}", value, e))
+}
@@ -0,0 +1,153 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::fmt::{Display, Formatter};
+use std::hint;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Deadlines further behind than this many intervals are dropped instead of catching up with a burst of frames.
+const MAX_BACKLOG: u32 = 10;
+
+/// Paces frames against absolute deadlines, so send time and oversleeping do not add up.
+///
+/// Frame `n` is due at `start + n * interval`, if a frame is late the following frames are sent earlier to catch up.
+pub struct Pacer {
+    start: Instant,
+    interval: Duration,
+    /// Time before a deadline which is busy-waited instead of slept.
+    busy_wait: Duration,
+    /// Index of the next frame.
+    frame: u32,
+    stats: PacingStats,
+}
+
+/// Measurements of the [Pacer].
+#[derive(Default)]
+pub struct PacingStats {
+    pub frames: u64,
+    /// Sum of the lateness of all frames.
+    pub total_jitter: Duration,
+    pub max_jitter: Duration,
+    /// Times the schedule was reset after falling too far behind.
+    pub resets: u64,
+    first: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl Pacer {
+    pub fn new(interval: Duration, busy_wait: Duration) -> Self {
+        Pacer { start: Instant::now(), interval, busy_wait, frame: 0, stats: PacingStats::default() }
+    }
+
+    /// Waits for the deadline of the next frame.
+    pub fn wait(&mut self) {
+        let deadline = self.next_deadline(Instant::now());
+        if let Some(sleep) = deadline.checked_duration_since(Instant::now()).and_then(|d| d.checked_sub(self.busy_wait)) {
+            thread::sleep(sleep);
+        }
+        while Instant::now() < deadline {
+            hint::spin_loop();
+        }
+        let now = Instant::now();
+        self.stats.record(now, now - deadline);
+        self.frame += 1;
+    }
+
+    /// The deadline of the next frame, the schedule is reset to `now` if it fell too far behind.
+    fn next_deadline(&mut self, now: Instant) -> Instant {
+        let deadline = self.start + self.interval * self.frame;
+        if now > deadline + self.interval * MAX_BACKLOG {
+            self.start = now;
+            self.frame = 0;
+            self.stats.resets += 1;
+            return now;
+        }
+        deadline
+    }
+
+    pub fn into_stats(self) -> PacingStats {
+        self.stats
+    }
+}
+
+impl PacingStats {
+    fn record(&mut self, time: Instant, jitter: Duration) {
+        self.frames += 1;
+        self.total_jitter += jitter;
+        self.max_jitter = self.max_jitter.max(jitter);
+        self.first.get_or_insert(time);
+        self.last = Some(time);
+    }
+
+    /// The average time between two frames.
+    pub fn effective_interval(&self) -> Option<Duration> {
+        match (self.first, self.last) {
+            (Some(first), Some(last)) if self.frames > 1 => Some(div(last - first, self.frames - 1)),
+            _ => None,
+        }
+    }
+
+    pub fn mean_jitter(&self) -> Duration {
+        if self.frames == 0 { Duration::ZERO } else { div(self.total_jitter, self.frames) }
+    }
+}
+
+/// Divides in nanoseconds, a frame count beyond [u32] would be truncated by `Duration / u32`.
+fn div(duration: Duration, count: u64) -> Duration {
+    Duration::from_nanos((duration.as_nanos() / count as u128) as u64)
+}
+
+impl Display for PacingStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "effective interval: {} µs\tjitter: mean {} µs, max {} µs\tschedule resets: {}",
+               self.effective_interval().map_or("-".to_string(), |i| i.as_micros().to_string()),
+               self.mean_jitter().as_micros(), self.max_jitter.as_micros(), self.resets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deadlines() {
+        let interval = Duration::from_millis(2);
+        let mut pacer = Pacer::new(interval, Duration::ZERO);
+        let start = pacer.start;
+        for n in 0..5 {
+            // late frames do not shift the following deadlines
+            assert_eq!(start + interval * n, pacer.next_deadline(start + interval * n + interval / 2));
+            pacer.frame += 1;
+        }
+        let late = start + interval * (5 + MAX_BACKLOG + 1);
+        assert_eq!(late, pacer.next_deadline(late));
+        assert_eq!(1, pacer.stats.resets);
+        pacer.frame += 1;
+        assert_eq!(late + interval, pacer.next_deadline(late));
+    }
+
+    #[test]
+    fn stats() {
+        let interval = Duration::from_millis(2);
+        let mut pacer = Pacer::new(interval, Duration::ZERO);
+        let start = pacer.start;
+        for n in 0..50 {
+            // frames sent 300 µs after their deadline, without sleeping
+            let sent = pacer.next_deadline(start + interval * n) + Duration::from_micros(300);
+            pacer.stats.record(sent, Duration::from_micros(300));
+            pacer.frame += 1;
+        }
+        let stats = pacer.into_stats();
+        assert_eq!(50, stats.frames);
+        assert_eq!(0, stats.resets);
+        assert_eq!(Some(interval), stats.effective_interval());
+        assert_eq!(Duration::from_micros(300), stats.mean_jitter());
+        assert_eq!(Duration::from_micros(300), stats.max_jitter);
+    }
+
+    #[test]
+    fn many_frames() {
+        let stats = PacingStats { frames: 1 << 32, total_jitter: Duration::from_secs(1 << 32), ..Default::default() };
+        assert_eq!(Duration::from_secs(1), stats.mean_jitter());
+    }
+}
@@ -1,14 +1,18 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use std::cmp::min;
+use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::ops::BitOr;
 use rc4::{KeyInit, Rc4, StreamCipher};
 use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 use crate::MacAddress;
 use crate::pcd::CardType::{Accessory, AzureFlute, Item, ManaphyEgg, MemberCard, OaksLetter, Pokemon, PokemonEgg, PoketchApp, PokewalkerArea, Rule, Seal, Secretkey, Unknown};
 use crate::pcd::Game::{Diamond, HeartGold, Pearl, Platinum, SoulSilver};
 use crate::pokestr::{DecodeError, Gen4Str, STRING_TERMINATOR};
+use crate::date;
+use crate::date::Date;
 use crate::species::species_by_pokedex;
 
 pub const PCD_LENGTH: usize = PCD_PGT_LENGTH + PCD_HEADER_LENGTH + PCD_CARD_DATA_LENGTH;
@@ -22,11 +26,17 @@ pub const PCD_FRAGMENT_LENGTH: usize = PCD_EXTENDED_LENGTH / (PCD_FRAGMENTS - 1)
 
 /// Attribute const offsets are absolute to pcd raw data
 pub const PCD_CARD_TYPE_OFFSET: usize = 0x0;
+pub const PCD_CARD_SLOT_OFFSET: usize = 0x2;
+pub const PCD_CARD_USED_OFFSET: usize = 0x3;
 pub const PCD_CARD_GIFT_INSTANCE_OFFSET: usize = 0x4;
 pub const PCD_TITLE_OFFSET: usize = 0x104;
 
 pub const PCD_CARD_ID_OFFSET: usize = 0x150;
 pub const PCD_GAMES_OFFSET: usize = 0x14c;
+pub const PCD_CARD_FLAGS_OFFSET: usize = 0x152;
+pub const PCD_DELIVERY_OFFSET: usize = 0x153;
+/// Bit of the card flags which allows sharing the card with other players
+pub const PCD_SHAREABLE_FLAG: u8 = 0x1;
 
 /// Length in u16 units inclusive termination
 pub const PCD_TITLE_MAX_LENGTH: usize = (PCD_GAMES_OFFSET - PCD_TITLE_OFFSET) / 2;
@@ -42,6 +52,10 @@ pub const PCD_RECEIVED_OFFSET: usize = 0x354;
 
 pub const PCD_REDISTRIBUTION_OFFSET: usize = 0x348;
 
+/// Regions without any known meaning as `(offset, length)`, which are expected to be zero
+pub const PCD_PADDING_REGIONS: [(usize, usize); 5] = [(0x1, 1), (0x14e, 2), (0x349, 1), (0x350, 4), (0x356, 2)];
+pub const PCD_PADDING_LENGTH: usize = 10;
+
 pub type PCDFragment = [u8; PCD_FRAGMENT_LENGTH];
 pub type PCDHeader = [u8; PCD_HEADER_LENGTH];
 
@@ -71,7 +85,7 @@ pub struct Extended {
 }
 
 #[repr(u8)]
-#[derive(Copy, Clone, Debug, ValueEnum)]
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum, Serialize, Deserialize)]
 pub enum CardType {
     None = 0x0,
     Pokemon = 0x1,
@@ -113,7 +127,7 @@ impl TryFrom<u8> for CardType {
 }
 
 #[repr(u16)]
-#[derive(Copy, Clone, Debug, ValueEnum)]
+#[derive(Copy, Clone, Debug, ValueEnum, Serialize, Deserialize)]
 pub enum Game {
     Diamond = 1 << 2,
     Pearl = 1 << 3,
@@ -134,6 +148,9 @@ fn serialize_games(games: &[Game]) -> u16 {
     games.iter().fold(0, |a, &g| a.bitor(g as u16))
 }
 
+/// Bits of the games field which are covered by [Game].
+const KNOWN_GAMES_MASK: u16 = Diamond as u16 | Pearl as u16 | Platinum as u16 | HeartGold as u16 | SoulSilver as u16;
+
 
 pub struct Deserialized {
     pub title: String,
@@ -141,11 +158,25 @@ pub struct Deserialized {
     pub gift_instance: u16,
     pub card_id: u16,
     pub games: Vec<Game>,
+    /// Bits of the games field not covered by [Game], retained as is
+    pub unknown_games: u16,
     pub comment: String,
     pub redistribution: u8,
     pub icons: (u16, u16, u16),
     pub pgt: [u8; PCD_PGT_LENGTH],
     pub received: u16,
+    /// Slot of the card in the card album
+    pub slot: u8,
+    pub used: bool,
+    pub shareable: bool,
+    /// Remaining bits of the card flags without the shareable flag
+    pub card_flags: u8,
+    pub delivery: u8,
+    /// Content of the [PCD_PADDING_REGIONS] concatenated
+    pub padding: [u8; PCD_PADDING_LENGTH],
+    /// The raw sections the fields are read from and written onto, to retain everything not modeled by the fields.
+    header: PCDHeader,
+    card_data: [u8; PCD_CARD_DATA_LENGTH],
 }
 
 impl<'a> TryFrom<&'a [u8]> for PCD<Raw> {
@@ -225,24 +256,35 @@ pub fn pgt_info(pgt: &[u8; PCD_PGT_LENGTH]) -> (CardType, u16) {
 
 impl PCD<Partitioned> {
     pub fn deserialize(self) -> PCD<Deserialized> {
+        let raw = self.data();
         let header: Vec<u16> = self.state.header.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
         let card_data: Vec<u16> = self.state.card_data.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
 
         let pgt = pgt_info(&self.state.pgt);
 
         let icons_offset_rela = (PCD_ICONS_OFFSET - PCD_COMMENT_OFFSET) / 2;
+        let games = header[(PCD_GAMES_OFFSET - PCD_TITLE_OFFSET) / 2].rotate_left(8);
 
         let des = Deserialized {
             title: (&first_str(&self.state.header, PCD_TITLE_MAX_LENGTH)).try_into().unwrap_or_else(|e: DecodeError| e.escaped),
             card_type: pgt.0,
             gift_instance: pgt.1,
             card_id: header[(PCD_CARD_ID_OFFSET - PCD_TITLE_OFFSET) / 2],
-            games: Game::parse(header[(PCD_GAMES_OFFSET - PCD_TITLE_OFFSET) / 2].rotate_left(8)),
+            games: Game::parse(games),
+            unknown_games: games & !KNOWN_GAMES_MASK,
             comment: (&first_str(&self.state.card_data, PCD_COMMENT_MAX_LENGTH)).try_into().unwrap_or_else(|e: DecodeError| e.escaped),
             redistribution: self.state.card_data[PCD_REDISTRIBUTION_OFFSET - PCD_COMMENT_OFFSET],
             icons: (card_data[icons_offset_rela], card_data[icons_offset_rela + 1], card_data[icons_offset_rela + 2]),
             pgt: self.state.pgt,
             received: card_data[(PCD_RECEIVED_OFFSET - PCD_COMMENT_OFFSET) / 2],
+            slot: self.state.pgt[PCD_CARD_SLOT_OFFSET],
+            used: self.state.pgt[PCD_CARD_USED_OFFSET] > 0,
+            shareable: raw[PCD_CARD_FLAGS_OFFSET] & PCD_SHAREABLE_FLAG > 0,
+            card_flags: raw[PCD_CARD_FLAGS_OFFSET] & !PCD_SHAREABLE_FLAG,
+            delivery: raw[PCD_DELIVERY_OFFSET],
+            padding: PCD_PADDING_REGIONS.iter().flat_map(|&(o, l)| raw[o..o + l].to_vec()).collect::<Vec<u8>>().try_into().unwrap(),
+            header: self.state.header,
+            card_data: self.state.card_data,
         };
         PCD { state: des }
     }
@@ -255,7 +297,7 @@ impl PCD<Partitioned> {
 ///
 /// ## Panic
 /// Since for every character in [Gen4Str] two bytes are required, this will panic on odd `data` length.
-fn first_str(data: &[u8], max_len: usize) -> Gen4Str {
+pub(crate) fn first_str(data: &[u8], max_len: usize) -> Gen4Str {
     let chunks = data.chunks_exact(2).map(|c| u16::from_le_bytes(c.try_into().expect("string must have even u8s")));
     let mut chunks_clone = chunks.clone();
     let len = min(max_len, chunks_clone.position(|e| e == STRING_TERMINATOR).unwrap_or(max_len));
@@ -274,29 +316,55 @@ impl PCD<Deserialized> {
                 gift_instance: 0,
                 card_id: 0,
                 games: vec![],
+                unknown_games: 0,
                 comment: "".to_string(),
                 redistribution: 0,
                 icons: (0, 0, 0),
                 pgt: [0x00; PCD_PGT_LENGTH],
                 received: 0,
+                slot: 0,
+                used: false,
+                shareable: false,
+                card_flags: 0,
+                delivery: 0,
+                padding: [0x00; PCD_PADDING_LENGTH],
+                header: [0x00; PCD_HEADER_LENGTH],
+                card_data: [0x00; PCD_CARD_DATA_LENGTH],
             }
         }
     }
 
+    /// Writes the fields onto the raw sections they were read from.
+    /// Bytes which are not covered by any field, unknown game bits and unknown card types are retained,
+    /// as well as strings which were not changed, thus an unmodified card serializes to identical data.
     pub fn serialize(&self) -> PCD<Partitioned> {
         let des = &self.state;
-        let mut header = [0x00u8; PCD_HEADER_LENGTH];
-        let mut card_data = [0x00u8; PCD_CARD_DATA_LENGTH];
-        let mut pgt = des.pgt.clone();
+        let original = PCD { state: Partitioned { pgt: des.pgt, header: des.header, card_data: des.card_data } }.deserialize().state;
+        let mut header = des.header;
+        let mut card_data = des.card_data;
+        let mut pgt = des.pgt;
 
-        pgt[PCD_CARD_TYPE_OFFSET] = des.card_type as u8;
+        if des.card_type != original.card_type {
+            pgt[PCD_CARD_TYPE_OFFSET] = des.card_type as u8;
+        }
         pgt[PCD_CARD_GIFT_INSTANCE_OFFSET..PCD_CARD_GIFT_INSTANCE_OFFSET + 2].copy_from_slice(&des.gift_instance.to_le_bytes());
+        pgt[PCD_CARD_SLOT_OFFSET] = des.slot;
+        if des.used != original.used {
+            pgt[PCD_CARD_USED_OFFSET] = des.used as u8;
+        }
 
-        put_str(&mut header, &des.title, PCD_TITLE_MAX_LENGTH);
+        if des.title != original.title {
+            put_str(&mut header, &des.title, PCD_TITLE_MAX_LENGTH);
+        }
         header[PCD_CARD_ID_OFFSET - PCD_PGT_LENGTH..PCD_CARD_ID_OFFSET + 2 - PCD_PGT_LENGTH].copy_from_slice(&des.card_id.to_le_bytes());
-        header[PCD_GAMES_OFFSET - PCD_PGT_LENGTH..PCD_GAMES_OFFSET + 2 - PCD_PGT_LENGTH].copy_from_slice(&serialize_games(&des.games).to_be_bytes());
+        let games = serialize_games(&des.games) | des.unknown_games & !KNOWN_GAMES_MASK;
+        header[PCD_GAMES_OFFSET - PCD_PGT_LENGTH..PCD_GAMES_OFFSET + 2 - PCD_PGT_LENGTH].copy_from_slice(&games.to_be_bytes());
+        header[PCD_CARD_FLAGS_OFFSET - PCD_PGT_LENGTH] = des.card_flags & !PCD_SHAREABLE_FLAG | if des.shareable { PCD_SHAREABLE_FLAG } else { 0 };
+        header[PCD_DELIVERY_OFFSET - PCD_PGT_LENGTH] = des.delivery;
 
-        put_str(&mut card_data, &des.comment, PCD_COMMENT_MAX_LENGTH);
+        if des.comment != original.comment {
+            put_str(&mut card_data, &des.comment, PCD_COMMENT_MAX_LENGTH);
+        }
 
         let icons = [des.icons.0,des.icons.1,des.icons.2].iter().flat_map(|i|i.to_le_bytes()).collect::<Vec<u8>>();
 
@@ -305,6 +373,18 @@ impl PCD<Deserialized> {
         card_data[PCD_RECEIVED_OFFSET - PCD_COMMENT_OFFSET..PCD_RECEIVED_OFFSET - PCD_COMMENT_OFFSET + 2].copy_from_slice(&des.received.to_le_bytes());
         card_data[PCD_REDISTRIBUTION_OFFSET - PCD_COMMENT_OFFSET] = des.redistribution;
 
+        let mut padding = des.padding.iter();
+        for (offset, length) in PCD_PADDING_REGIONS {
+            for i in offset..offset + length {
+                let b = *padding.next().unwrap();
+                match i {
+                    i if i < PCD_PGT_LENGTH => pgt[i] = b,
+                    i if i < PCD_PGT_LENGTH + PCD_HEADER_LENGTH => header[i - PCD_PGT_LENGTH] = b,
+                    i => card_data[i - PCD_PGT_LENGTH - PCD_HEADER_LENGTH] = b,
+                }
+            }
+        }
+
         PCD {
             state: Partitioned {
                 pgt,
@@ -316,40 +396,19 @@ impl PCD<Deserialized> {
 
     /// Calculates the received date and returns it as a tuple.
     /// Representation: (year, month of year, day of month)
-    pub fn received(&self) -> (u16, u8, u8) {
-        let approx_years = self.state.received / 365; // works until 2365, max year un u16 days is 2179
-        let corrected_days = self.state.received - approx_years / 4 + approx_years / 100 - approx_years / 400 - 1;
-
-        let years = corrected_days / 365;
-        let is_leap = (years % 4 == 0 && years % 100 != 0) || years % 400 == 0;
-        let remaining_days = corrected_days - years * 365 + if is_leap { 1 } else { 0 };
-
-        let mut month = 0;
-        let mut day_acc = 0;
-        let mut day_acc_limited = 0;
-
-        while day_acc <= remaining_days {
-            day_acc_limited = day_acc;
-            day_acc += match month {
-                0 | 2 | 4 | 6 | 7 | 9 | 11 => 31,
-                1 => if is_leap { 29 } else { 28 },
-                _ => 30
-            };
-            month += 1;
-        }
-
-        (years + 2000, month, (remaining_days - day_acc_limited + 1) as u8)
+    pub fn received(&self) -> Date {
+        date::from_days(self.state.received)
     }
 }
 
-fn put_str(dest: &mut [u8], str: &String, max_len: usize) {
+pub(crate) fn put_str(dest: &mut [u8], str: &String, max_len: usize) {
     let enc_res = Gen4Str::try_from(str);
     let enc = enc_res.unwrap_or_else(|err| {
         eprintln!("Warning: invalid character '{}' on index {}, invalid characters will be skipped", err.char, err.idx);
         err.sanitized
     }).vec;
     let len = min(enc.len(), max_len - 1) * 2;
-    dest[..len].copy_from_slice(&enc.iter().flat_map(|c| c.to_le_bytes()).collect::<Vec<u8>>());
+    dest[..len].copy_from_slice(&enc.iter().take(len / 2).flat_map(|c| c.to_le_bytes()).collect::<Vec<u8>>());
     dest[len..max_len * 2].copy_from_slice(&vec![0xffu8; max_len * 2 - len])
 }
 
@@ -360,16 +419,51 @@ impl Display for PCD<Deserialized> {
             species_by_pokedex(self.state.icons.1 as usize).unwrap_or("None"),
             species_by_pokedex(self.state.icons.2 as usize).unwrap_or("None")
         );
-        let (year, month, day) = self.received();
+        let received = date::fmt(self.received());
+        let plausibility = date::implausibility(self.state.received).map(|r| format!(" (impossible, {})", r)).unwrap_or_default();
         write!(f, "title: {}\ticons: {}({}),{}({}),{}({})\n\
         type: {:?}\tinstance: {}\tcard ID: {}\n\n\
         {}\n\n\
         games: {:?}\n\
-        redistribution limit: {}{}\n\
-        received: {}-{:02}-{:02}\n", self.state.title, icon_names.0, self.state.icons.0, icon_names.1, self.state.icons.1, icon_names.2, self.state.icons.2, self.state.card_type, self.state.gift_instance, self.state.card_id, self.state.comment, self.state.games, self.state.redistribution, if self.state.redistribution == 0xff { "(unlimited)" } else { "" }, year, month, day)
+        redistribution limit: {}{}\tshareable: {}\n\
+        received: {}{}\n\
+        slot: {}\tused: {}\tflags: {:02x}\tdelivery: {:02x}\n", self.state.title, icon_names.0, self.state.icons.0, icon_names.1, self.state.icons.1, icon_names.2, self.state.icons.2, self.state.card_type, self.state.gift_instance, self.state.card_id, self.state.comment, self.state.games, self.state.redistribution, if self.state.redistribution == 0xff { "(unlimited)" } else { "" }, self.state.shareable, received, plausibility, self.state.slot, self.state.used, self.state.card_flags, self.state.delivery)?;
+        if self.state.padding.iter().any(|&b| b > 0) {
+            writeln!(f, "padding: {:02x?}", self.state.padding)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reasons why decrypted data is no valid wondercard, usually because of a wrong address or checksum.
+#[derive(Debug, PartialEq)]
+pub enum IntegrityError {
+    /// The header and its duplicate differ.
+    HeaderMismatch,
+    /// The checksum of the decrypted data could not be calculated.
+    ChecksumFailed(String),
+    /// The checksum of the decrypted data differs from the checksum used for decryption.
+    ChecksumMismatch { expected: u16, actual: u16 },
+    /// The card type of the PGT is unknown.
+    InvalidCardType(u8),
+    /// The title lacks the string terminator.
+    UnterminatedTitle,
+}
+
+impl Display for IntegrityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntegrityError::HeaderMismatch => f.write_str("the header does not match its duplicate"),
+            IntegrityError::ChecksumFailed(e) => write!(f, "the checksum could not be calculated: {}", e),
+            IntegrityError::ChecksumMismatch { expected, actual } => write!(f, "the checksum is {:04x} instead of {:04x}", actual, expected),
+            IntegrityError::InvalidCardType(t) => write!(f, "the card type {:02x} is invalid", t),
+            IntegrityError::UnterminatedTitle => f.write_str("the title is not terminated"),
+        }
     }
 }
 
+impl Error for IntegrityError {}
+
 impl From<PCD<Partitioned>> for PCD<Extended> {
     fn from(value: PCD<Partitioned>) -> PCD<Extended> {
         let part = value.state;
@@ -438,6 +532,37 @@ impl PCD<Extended> {
         Ok(PCD::<Encrypted>::new(sized_data))
     }
 
+    /// Verifies that the data was decrypted with the correct address and checksum.
+    ///
+    /// Both header copies have to match, the checksum has to be recomputed
+    /// and the card type and the title have to be valid.
+    ///
+    /// # Arguments
+    ///
+    /// * `checksum` - The checksum the data was decrypted with.
+    ///
+    /// # Returns
+    ///
+    /// Returns the first failed check as [IntegrityError].
+    ///
+    pub fn verify(&self, checksum: u16) -> Result<(), IntegrityError> {
+        let state = &self.state;
+        if state.header != state.header_duplicate {
+            return Err(IntegrityError::HeaderMismatch);
+        }
+        let actual = self.checksum().map_err(IntegrityError::ChecksumFailed)?;
+        if actual != checksum {
+            return Err(IntegrityError::ChecksumMismatch { expected: checksum, actual });
+        }
+        if CardType::try_from(state.pgt[PCD_CARD_TYPE_OFFSET]).is_err() {
+            return Err(IntegrityError::InvalidCardType(state.pgt[PCD_CARD_TYPE_OFFSET]));
+        }
+        if !state.header[..PCD_TITLE_MAX_LENGTH * 2].chunks_exact(2).any(|c| u16::from_le_bytes([c[0], c[1]]) == STRING_TERMINATOR) {
+            return Err(IntegrityError::UnterminatedTitle);
+        }
+        Ok(())
+    }
+
     /// Go back to the [PCD<Partitioned>] state.
     ///
     pub fn simplify(self) -> PCD<Partitioned> {
@@ -468,6 +593,10 @@ impl PCD<Encrypted> {
         }
     }
 
+    pub fn data(&self) -> Vec<u8> {
+        self.state.data.to_vec()
+    }
+
     /// Fragment the encrypted data.
     ///
     /// Split the payload into the desired fragment sizes without the header.
@@ -492,29 +621,50 @@ impl PCD<Encrypted> {
     ///
     /// Returns a [PCD<Extended>] instance containing the decrypted PCD data.
     ///
-    pub fn decrypt(self, address: &MacAddress, checksum: u16) -> PCD<Extended> {
+    pub fn decrypt(&self, address: &MacAddress, checksum: u16) -> PCD<Extended> {
         let key = key(address, checksum);
         let mut rc4 = Rc4::new(&key.into());
         let mut data = self.state.data;
         rc4.apply_keystream(data.as_mut());
         let mut header: [u8; PCD_HEADER_LENGTH] = [0; PCD_HEADER_LENGTH];
         let mut pgt: [u8; PCD_PGT_LENGTH] = [0; PCD_PGT_LENGTH];
+        let mut header_duplicate: [u8; PCD_HEADER_LENGTH] = [0; PCD_HEADER_LENGTH];
         let mut card_data: [u8; PCD_CARD_DATA_LENGTH] = [0; PCD_CARD_DATA_LENGTH];
         header.copy_from_slice(&data[0..PCD_HEADER_LENGTH]);
         pgt.copy_from_slice(&data[PCD_HEADER_LENGTH..PCD_PGT_LENGTH + PCD_HEADER_LENGTH]);
+        header_duplicate.copy_from_slice(&data[PCD_PGT_LENGTH + PCD_HEADER_LENGTH..PCD_PGT_LENGTH + 2 * PCD_HEADER_LENGTH]);
         card_data.copy_from_slice(&data[2 * PCD_HEADER_LENGTH + PCD_PGT_LENGTH..PCD_CARD_DATA_LENGTH + PCD_PGT_LENGTH + 2 * PCD_HEADER_LENGTH]);
         PCD {
             state: Extended {
                 header,
                 pgt,
-                header_duplicate: header,
+                header_duplicate,
                 card_data,
             }
         }
     }
+
+    /// Whether the header and its duplicate are equal when decrypted with the checksum,
+    /// a cheap test of a checksum since only the data up to the duplicate is decrypted.
+    pub fn headers_match(&self, address: &MacAddress, checksum: u16) -> bool {
+        let mut data: [u8; PCD_PGT_LENGTH + 2 * PCD_HEADER_LENGTH] = self.state.data[..PCD_PGT_LENGTH + 2 * PCD_HEADER_LENGTH].try_into().unwrap();
+        Rc4::new(&key(address, checksum).into()).apply_keystream(&mut data);
+        data[..PCD_HEADER_LENGTH] == data[PCD_PGT_LENGTH + PCD_HEADER_LENGTH..]
+    }
 }
 
-fn key(address: &MacAddress, checksum: u16) -> [u8; 8] {
+/// Derives the RC4 key of the distribution from the Ethernet address and the checksum.
+///
+/// # Arguments
+///
+/// * `address` - A reference to a [MacAddress] representing the Ethernet address.
+/// * `checksum` - The checksum of the extended PCD.
+///
+/// # Returns
+///
+/// Returns the 8 byte key.
+///
+pub fn key(address: &MacAddress, checksum: u16) -> [u8; 8] {
     let checksum_parts = checksum.to_le_bytes();
     let mut key = [address[0], address[1], checksum_parts[0], checksum_parts[1], address[4], address[5], address[2], address[3]];
     let mut hw_low = 0xa2u8;
@@ -544,9 +694,52 @@ mod tests {
     fn date_deserialization() {
         let mut pcd = PCD::<Deserialized>::new();
 
-        assert_eq!((2179, 6, 6), pcd.received());
+        assert_eq!((2000, 1, 1), pcd.received());
 
         pcd.state.received = 8982;
         assert_eq!((2024, 8, 4), pcd.received());
     }
+
+    #[test]
+    fn unmodified_round_trip() {
+        let mut data = [0u8; PCD_LENGTH];
+        let mut seed = 0x1234_5678u32;
+        for b in data.iter_mut() {
+            seed = seed.wrapping_mul(0x41c64e6d).wrapping_add(0x6073);
+            *b = (seed >> 24) as u8;
+        }
+        data[PCD_CARD_TYPE_OFFSET] = 0x20;
+
+        let pcd: PCD<Raw> = PCD::try_from(data.as_slice()).unwrap();
+        let des = PCD::<Partitioned>::from(pcd).deserialize();
+        assert_eq!(CardType::Unknown, des.state.card_type);
+        let serialized: [u8; PCD_LENGTH] = PCD::<Raw>::from(&des.serialize()).into();
+        assert_eq!(data, serialized);
+    }
+
+    #[test]
+    fn key_derivation() {
+        assert_eq!([0x06, 0xff, 0xb2, 0xe3, 0xc4, 0x63, 0x25, 0x0d], key(&[0xa4, 0xc0, 0xe1, 0x6e, 0x76, 0x80], 0x1cb4));
+    }
+
+    #[test]
+    fn verify_decryption() {
+        let address = [0xa4, 0xc0, 0xe1, 0x6e, 0x76, 0x80];
+        let mut pcd = PCD::<Deserialized>::new();
+        pcd.state.title = "Gift".to_string();
+        pcd.state.card_type = Item;
+        let extended: PCD<Extended> = pcd.serialize().into();
+        let checksum = extended.checksum().unwrap();
+        let encrypted = extended.encrypt(&address).unwrap();
+
+        assert_eq!(Ok(()), encrypted.decrypt(&address, checksum).verify(checksum));
+        assert_eq!(Err(IntegrityError::HeaderMismatch), encrypted.decrypt(&[0xa4, 0xc0, 0xe1, 0x6e, 0x76, 0x81], checksum).verify(checksum));
+
+        let mut partitioned = PCD::<Deserialized>::new().serialize();
+        partitioned.state.pgt[PCD_CARD_TYPE_OFFSET] = 0x20;
+        let extended: PCD<Extended> = partitioned.into();
+        let checksum = extended.checksum().unwrap();
+        assert_eq!(Err(IntegrityError::InvalidCardType(0x20)), extended.verify(checksum));
+        assert!(matches!(extended.verify(checksum ^ 1), Err(IntegrityError::ChecksumMismatch { .. })));
+    }
 }
\ No newline at end of file
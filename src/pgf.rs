@@ -0,0 +1,342 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::cmp::min;
+use std::fmt::{Display, Formatter};
+use std::ops::BitOr;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::date::Date;
+use crate::pk4::{BALLS, language_name, NATURES};
+use crate::species::species_by_pokedex;
+
+/// Generation V wonder cards have a fixed size of 204 bytes.
+pub const PGF_LENGTH: usize = 0xcc;
+
+/// Attribute const offsets are absolute to pgf raw data
+pub const PGF_OT_ID_OFFSET: usize = 0x0;
+/// Items and entree powers store their id at the start instead of the trainer id
+pub const PGF_GIFT_ID_OFFSET: usize = 0x0;
+pub const PGF_OT_SECRET_ID_OFFSET: usize = 0x2;
+pub const PGF_PID_OFFSET: usize = 0x8;
+pub const PGF_RIBBONS_OFFSET: usize = 0xc;
+pub const PGF_BALL_OFFSET: usize = 0xe;
+pub const PGF_HELD_ITEM_OFFSET: usize = 0x10;
+pub const PGF_MOVES_OFFSET: usize = 0x12;
+pub const PGF_SPECIES_OFFSET: usize = 0x1a;
+pub const PGF_FORM_OFFSET: usize = 0x1c;
+pub const PGF_LANGUAGE_OFFSET: usize = 0x1d;
+pub const PGF_NICKNAME_OFFSET: usize = 0x1e;
+/// Length in u16 units inclusive termination
+pub const PGF_NICKNAME_MAX_LENGTH: usize = 0xb;
+pub const PGF_NATURE_OFFSET: usize = 0x34;
+pub const PGF_MET_LEVEL_OFFSET: usize = 0x3c;
+/// Order: HP, Attack, Defense, Speed, Special Attack, Special Defense
+pub const PGF_IVS_OFFSET: usize = 0x43;
+pub const PGF_OT_NAME_OFFSET: usize = 0x4a;
+/// Length in u16 units inclusive termination
+pub const PGF_OT_NAME_MAX_LENGTH: usize = 0x8;
+pub const PGF_LEVEL_OFFSET: usize = 0x5b;
+pub const PGF_EGG_OFFSET: usize = 0x5c;
+
+pub const PGF_TITLE_OFFSET: usize = 0x60;
+/// Length in u16 units inclusive termination
+pub const PGF_TITLE_MAX_LENGTH: usize = 0x25;
+pub const PGF_RECEIVED_OFFSET: usize = 0xac;
+pub const PGF_CARD_ID_OFFSET: usize = 0xb0;
+pub const PGF_CARD_TYPE_OFFSET: usize = 0xb3;
+pub const PGF_GAMES_OFFSET: usize = 0xb5;
+
+/// Value of the nature and IVs which lets the game choose randomly.
+pub const PGF_RANDOM: u8 = 0xff;
+
+pub const PGF_STRING_TERMINATOR: u16 = 0xffff;
+
+pub struct PGF<State> {
+    pub(crate) state: State,
+}
+
+pub struct Raw {
+    data: [u8; PGF_LENGTH],
+}
+
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+pub enum PGFCardType {
+    Pokemon = 0x1,
+    Item = 0x2,
+    Power = 0x3,
+}
+
+impl TryFrom<u8> for PGFCardType {
+    type Error = ();
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        [PGFCardType::Pokemon, PGFCardType::Item, PGFCardType::Power].into_iter().find(|&t| t as u8 == value).ok_or(())
+    }
+}
+
+/// Generation V games, an empty set means no restriction.
+#[repr(u8)]
+#[derive(Copy, Clone, Debug, ValueEnum, Serialize)]
+pub enum PGFGame {
+    White = 1 << 0,
+    Black = 1 << 1,
+    White2 = 1 << 2,
+    Black2 = 1 << 3,
+}
+
+impl PGFGame {
+    pub fn parse(n: u8) -> Vec<Self> {
+        [PGFGame::White, PGFGame::Black, PGFGame::White2, PGFGame::Black2].into_iter().filter(|&g| g as u8 & n > 0).collect()
+    }
+}
+
+pub struct Deserialized {
+    pub title: String,
+    /// [None] for card types unknown to [PGFCardType], the raw value is retained
+    pub card_type: Option<PGFCardType>,
+    pub card_id: u16,
+    pub games: Vec<PGFGame>,
+    pub received: Date,
+    /// Item or entree power id, depending on the card type
+    pub gift_id: u16,
+    pub pokemon: PGFPokemon,
+    /// The raw data the fields are read from and written onto.
+    data: [u8; PGF_LENGTH],
+}
+
+pub struct PGFPokemon {
+    pub species: u16,
+    pub form: u8,
+    pub level: u8,
+    pub nickname: String,
+    pub ot_name: String,
+    pub ot_id: u16,
+    pub ot_secret_id: u16,
+    pub pid: u32,
+    pub moves: [u16; 4],
+    pub held_item: u16,
+    pub ball: u8,
+    pub language: u8,
+    /// [PGF_RANDOM] for a random nature
+    pub nature: u8,
+    /// [PGF_RANDOM] for a random value
+    pub ivs: [u8; 6],
+    pub ribbons: u16,
+    pub is_egg: bool,
+}
+
+impl<'a> TryFrom<&'a [u8]> for PGF<Raw> {
+    type Error = String;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let data: [u8; PGF_LENGTH] = <[u8; PGF_LENGTH]>::try_from(value).map_err(|_| format!("PGF size needs to be {}, but was: {}", PGF_LENGTH, value.len()))?;
+        Ok(PGF { state: Raw { data } })
+    }
+}
+
+impl From<PGF<Raw>> for [u8; PGF_LENGTH] {
+    fn from(value: PGF<Raw>) -> Self {
+        value.state.data
+    }
+}
+
+/// Reads an UTF-16 string terminated by [PGF_STRING_TERMINATOR] with at max `max_len` units.
+fn read_str(data: &[u8], max_len: usize) -> String {
+    let units: Vec<u16> = data.chunks_exact(2).take(max_len).map(|c| u16::from_le_bytes([c[0], c[1]])).take_while(|&c| c != PGF_STRING_TERMINATOR).collect();
+    String::from_utf16_lossy(&units)
+}
+
+/// Writes an UTF-16 string with termination and pads the remaining `max_len` units with [PGF_STRING_TERMINATOR].
+fn write_str(dest: &mut [u8], str: &str, max_len: usize) {
+    let units: Vec<u16> = str.encode_utf16().collect();
+    let len = min(units.len(), max_len - 1);
+    if len < units.len() {
+        eprintln!("Warning: '{}' exceeds {} characters and will be truncated", str, max_len - 1);
+    }
+    let padded = units.iter().take(len).copied().chain([PGF_STRING_TERMINATOR].repeat(max_len - len));
+    dest[..max_len * 2].copy_from_slice(&padded.flat_map(|c| c.to_le_bytes()).collect::<Vec<u8>>());
+}
+
+impl PGF<Raw> {
+    pub fn deserialize(&self) -> PGF<Deserialized> {
+        let data = &self.state.data;
+        let u16_at = |o: usize| u16::from_le_bytes([data[o], data[o + 1]]);
+        let des = Deserialized {
+            title: read_str(&data[PGF_TITLE_OFFSET..], PGF_TITLE_MAX_LENGTH),
+            card_type: PGFCardType::try_from(data[PGF_CARD_TYPE_OFFSET]).ok(),
+            card_id: u16_at(PGF_CARD_ID_OFFSET),
+            games: PGFGame::parse(data[PGF_GAMES_OFFSET]),
+            received: (u16_at(PGF_RECEIVED_OFFSET + 2), data[PGF_RECEIVED_OFFSET + 1], data[PGF_RECEIVED_OFFSET]),
+            gift_id: u16_at(PGF_GIFT_ID_OFFSET),
+            pokemon: PGFPokemon {
+                species: u16_at(PGF_SPECIES_OFFSET),
+                form: data[PGF_FORM_OFFSET],
+                level: data[PGF_LEVEL_OFFSET],
+                nickname: read_str(&data[PGF_NICKNAME_OFFSET..], PGF_NICKNAME_MAX_LENGTH),
+                ot_name: read_str(&data[PGF_OT_NAME_OFFSET..], PGF_OT_NAME_MAX_LENGTH),
+                ot_id: u16_at(PGF_OT_ID_OFFSET),
+                ot_secret_id: u16_at(PGF_OT_SECRET_ID_OFFSET),
+                pid: u32::from_le_bytes(data[PGF_PID_OFFSET..PGF_PID_OFFSET + 4].try_into().unwrap()),
+                moves: [0, 1, 2, 3].map(|i| u16_at(PGF_MOVES_OFFSET + 2 * i)),
+                held_item: u16_at(PGF_HELD_ITEM_OFFSET),
+                ball: data[PGF_BALL_OFFSET],
+                language: data[PGF_LANGUAGE_OFFSET],
+                nature: data[PGF_NATURE_OFFSET],
+                ivs: data[PGF_IVS_OFFSET..PGF_IVS_OFFSET + 6].try_into().unwrap(),
+                ribbons: u16_at(PGF_RIBBONS_OFFSET),
+                is_egg: data[PGF_EGG_OFFSET] > 0,
+            },
+            data: *data,
+        };
+        PGF { state: des }
+    }
+}
+
+impl PGF<Deserialized> {
+    /// Like [crate::pcd::PCD::serialize], but writes the Pokémon or the gift id depending on the card type, since they overlap.
+    pub fn serialize(&self) -> PGF<Raw> {
+        let des = &self.state;
+        let original = PGF { state: Raw { data: des.data } }.deserialize().state;
+        let mut data = des.data;
+        let put_u16 = |data: &mut [u8; PGF_LENGTH], o: usize, v: u16| data[o..o + 2].copy_from_slice(&v.to_le_bytes());
+
+        if des.title != original.title {
+            write_str(&mut data[PGF_TITLE_OFFSET..], &des.title, PGF_TITLE_MAX_LENGTH);
+        }
+        if let Some(t) = des.card_type {
+            data[PGF_CARD_TYPE_OFFSET] = t as u8;
+        }
+        put_u16(&mut data, PGF_CARD_ID_OFFSET, des.card_id);
+        data[PGF_GAMES_OFFSET] = des.games.iter().fold(0, |a, &g| a.bitor(g as u8));
+        put_u16(&mut data, PGF_RECEIVED_OFFSET + 2, des.received.0);
+        data[PGF_RECEIVED_OFFSET + 1] = des.received.1;
+        data[PGF_RECEIVED_OFFSET] = des.received.2;
+
+        if des.card_type == Some(PGFCardType::Pokemon) {
+            let pk = &des.pokemon;
+            put_u16(&mut data, PGF_SPECIES_OFFSET, pk.species);
+            data[PGF_FORM_OFFSET] = pk.form;
+            data[PGF_LEVEL_OFFSET] = pk.level;
+            if pk.level != original.pokemon.level {
+                data[PGF_MET_LEVEL_OFFSET] = pk.level;
+            }
+            if pk.nickname != original.pokemon.nickname {
+                write_str(&mut data[PGF_NICKNAME_OFFSET..], &pk.nickname, PGF_NICKNAME_MAX_LENGTH);
+            }
+            if pk.ot_name != original.pokemon.ot_name {
+                write_str(&mut data[PGF_OT_NAME_OFFSET..], &pk.ot_name, PGF_OT_NAME_MAX_LENGTH);
+            }
+            put_u16(&mut data, PGF_OT_ID_OFFSET, pk.ot_id);
+            put_u16(&mut data, PGF_OT_SECRET_ID_OFFSET, pk.ot_secret_id);
+            data[PGF_PID_OFFSET..PGF_PID_OFFSET + 4].copy_from_slice(&pk.pid.to_le_bytes());
+            pk.moves.iter().enumerate().for_each(|(i, &m)| put_u16(&mut data, PGF_MOVES_OFFSET + 2 * i, m));
+            put_u16(&mut data, PGF_HELD_ITEM_OFFSET, pk.held_item);
+            data[PGF_BALL_OFFSET] = pk.ball;
+            data[PGF_LANGUAGE_OFFSET] = pk.language;
+            data[PGF_NATURE_OFFSET] = pk.nature;
+            data[PGF_IVS_OFFSET..PGF_IVS_OFFSET + 6].copy_from_slice(&pk.ivs);
+            put_u16(&mut data, PGF_RIBBONS_OFFSET, pk.ribbons);
+            data[PGF_EGG_OFFSET] = pk.is_egg as u8;
+        } else {
+            put_u16(&mut data, PGF_GIFT_ID_OFFSET, des.gift_id);
+        }
+        PGF { state: Raw { data } }
+    }
+}
+
+/// Formats a value which may let the game choose randomly.
+fn random_or<T: Display>(value: u8, display: T) -> String {
+    if value == PGF_RANDOM { "random".to_string() } else { display.to_string() }
+}
+
+impl Display for PGF<Deserialized> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let des = &self.state;
+        let card_type = des.card_type.map(|t| format!("{:?}", t)).unwrap_or_else(|| format!("Unknown({})", des.data[PGF_CARD_TYPE_OFFSET]));
+        let (year, month, day) = des.received;
+        writeln!(f, "title: {}\ttype: {}\tcard ID: {}\n\n\
+        games: {}\n\
+        received: {}-{:02}-{:02}", des.title, card_type, des.card_id,
+                 if des.games.is_empty() { "all".to_string() } else { format!("{:?}", des.games) }, year, month, day)?;
+        match des.card_type {
+            Some(PGFCardType::Pokemon) => {
+                let pk = &des.pokemon;
+                let ivs: Vec<String> = pk.ivs.iter().map(|&iv| random_or(iv, iv)).collect();
+                write!(f, "\npokemon: {}({})\tlevel: {}\tnickname: {}{}\n\
+                OT: {} ({:05}/{:05})\tlanguage: {}({})\n\
+                nature: {}\tform: {}\theld item: {}\tball: {}({})\n\
+                moves: {}, {}, {}, {}\n\
+                IVs: {}\tribbons: {:04x}\n",
+                       species_by_pokedex(pk.species as usize).unwrap_or("None"), pk.species, pk.level, pk.nickname, if pk.is_egg { "\tegg" } else { "" },
+                       pk.ot_name, pk.ot_id, pk.ot_secret_id, language_name(pk.language), pk.language,
+                       random_or(pk.nature, NATURES.get(pk.nature as usize).unwrap_or(&"Unknown")), pk.form, pk.held_item, BALLS.get(pk.ball as usize).unwrap_or(&"Unknown"), pk.ball,
+                       pk.moves[0], pk.moves[1], pk.moves[2], pk.moves[3],
+                       ivs.join("/"), pk.ribbons)
+            }
+            Some(PGFCardType::Item) => writeln!(f, "item: {}", des.gift_id),
+            Some(PGFCardType::Power) => writeln!(f, "entree power: {}", des.gift_id),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pgf_edit_round_trip() {
+        let mut data = [0u8; PGF_LENGTH];
+        data[PGF_CARD_TYPE_OFFSET] = PGFCardType::Pokemon as u8;
+        data[PGF_GAMES_OFFSET] = PGFGame::Black as u8 | PGFGame::White as u8;
+        data[PGF_NATURE_OFFSET] = PGF_RANDOM;
+        write_str(&mut data[PGF_TITLE_OFFSET..], "Victini Gift", PGF_TITLE_MAX_LENGTH);
+        // not written again unless the title changes
+        data[PGF_TITLE_OFFSET + PGF_TITLE_MAX_LENGTH * 2 - 2] = 0x00;
+
+        let pgf = PGF::try_from(data.as_slice()).unwrap();
+        let mut des = pgf.deserialize();
+        assert_eq!("Victini Gift", des.state.title);
+        assert_eq!(data, <[u8; PGF_LENGTH]>::from(des.serialize()));
+
+        des.state.pokemon.species = 494;
+        des.state.pokemon.nickname = "Victini".to_string();
+        des.state.received = (2011, 3, 6);
+        let edited = des.serialize().deserialize();
+        assert_eq!(494, edited.state.pokemon.species);
+        assert_eq!("Victini", edited.state.pokemon.nickname);
+        assert_eq!((2011, 3, 6), edited.state.received);
+        assert_eq!(PGF_RANDOM, edited.state.pokemon.nature);
+    }
+
+    #[test]
+    fn pgf_fields_in_place() {
+        // filler, so bytes written at the wrong place are noticed
+        let mut data: [u8; PGF_LENGTH] = core::array::from_fn(|i| i as u8);
+        data[PGF_CARD_TYPE_OFFSET] = PGFCardType::Pokemon as u8;
+        data[PGF_GAMES_OFFSET] = PGFGame::Black2 as u8;
+        data[PGF_EGG_OFFSET] = 0;
+        write_str(&mut data[PGF_NICKNAME_OFFSET..], "Victiniiii", PGF_NICKNAME_MAX_LENGTH);
+        data[PGF_NATURE_OFFSET] = 3;
+        data[PGF_MET_LEVEL_OFFSET] = 15;
+        data[PGF_LEVEL_OFFSET] = 15;
+
+        let mut des = PGF::try_from(data.as_slice()).unwrap().deserialize();
+        assert_eq!("Victiniiii", des.state.pokemon.nickname);
+        assert_eq!(3, des.state.pokemon.nature);
+        assert_eq!(15, des.state.pokemon.level);
+        assert_eq!(data, <[u8; PGF_LENGTH]>::from(des.serialize()));
+
+        des.state.pokemon.nature = 10;
+        des.state.pokemon.level = 50;
+        let mut expected = data;
+        expected[PGF_NATURE_OFFSET] = 10;
+        expected[PGF_LEVEL_OFFSET] = 50;
+        expected[PGF_MET_LEVEL_OFFSET] = 50;
+        let edited: [u8; PGF_LENGTH] = des.serialize().into();
+        assert_eq!(expected, edited);
+        assert_eq!("Victiniiii", PGF::try_from(edited.as_slice()).unwrap().deserialize().state.pokemon.nickname);
+    }
+}
@@ -0,0 +1,85 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::fmt::{Display, Formatter};
+
+use crate::pcd::{CardType, Deserialized, Game, PCD, PCD_CARD_SLOT_OFFSET, PCD_CARD_USED_OFFSET, PCD_PGT_LENGTH, pgt_info};
+use crate::pk4::pgt_pokemon;
+use crate::species::species_by_pokedex;
+
+/// A bare PGT, the gift data of a PCD without title, description and icons.
+pub struct PGT {
+    pub(crate) data: [u8; PCD_PGT_LENGTH],
+}
+
+impl<'a> TryFrom<&'a [u8]> for PGT {
+    type Error = String;
+
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
+        let data: [u8; PCD_PGT_LENGTH] = <[u8; PCD_PGT_LENGTH]>::try_from(value).map_err(|_| format!("PGT size needs to be {}, but was: {}", PCD_PGT_LENGTH, value.len()))?;
+        Ok(PGT { data })
+    }
+}
+
+impl From<&PCD<Deserialized>> for PGT {
+    fn from(value: &PCD<Deserialized>) -> Self {
+        PGT { data: value.state.pgt }
+    }
+}
+
+impl PGT {
+    /// Wraps the PGT into a [PCD<Deserialized>] which can be received by all games.
+    ///
+    /// Title, description and icons are generated from the card type and, if any, the contained Pokémon.
+    pub fn wrap(&self) -> PCD<Deserialized> {
+        let (card_type, gift_instance) = pgt_info(&self.data);
+        let species = pgt_pokemon(&self.data, card_type).map(|pk| pk.decrypt().deserialize().state.species).unwrap_or(0);
+        let subject = match (species_by_pokedex(species as usize), card_type) {
+            (Some(name), CardType::Pokemon) => name.to_string(),
+            (Some(name), _) => format!("{} Egg", name),
+            (None, _) => gift_name(card_type).to_string(),
+        };
+
+        let mut pcd = PCD::<Deserialized>::new();
+        pcd.state.pgt = self.data;
+        pcd.state.card_type = card_type;
+        pcd.state.gift_instance = gift_instance;
+        pcd.state.slot = self.data[PCD_CARD_SLOT_OFFSET];
+        pcd.state.used = self.data[PCD_CARD_USED_OFFSET] > 0;
+        pcd.state.title = format!("{} Gift", subject);
+        pcd.state.comment = format!("This card holds a {} gift.\nBe sure to save your game after\nyou pick it up.", subject);
+        pcd.state.games = Game::parse(u16::MAX);
+        pcd.state.icons = (species, 0, 0);
+        pcd
+    }
+
+    pub fn data(&self) -> [u8; PCD_PGT_LENGTH] {
+        self.data
+    }
+}
+
+/// A human-readable name of the gift of the card type.
+fn gift_name(card_type: CardType) -> &'static str {
+    match card_type {
+        CardType::None => "Empty",
+        CardType::Pokemon => "Pokémon",
+        CardType::PokemonEgg | CardType::ManaphyEgg => "Egg",
+        CardType::Item => "Item",
+        CardType::Rule => "Battle Rule",
+        CardType::Seal => "Seal",
+        CardType::Accessory => "Accessory",
+        CardType::MemberCard => "Member Card",
+        CardType::OaksLetter => "Oak's Letter",
+        CardType::AzureFlute => "Azure Flute",
+        CardType::PoketchApp => "Pokétch App",
+        CardType::Secretkey => "Secret Key",
+        CardType::Unknown => "Mystery Gift",
+        CardType::PokewalkerArea => "Pokéwalker Area",
+    }
+}
+
+impl Display for PGT {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (card_type, gift_instance) = pgt_info(&self.data);
+        writeln!(f, "type: {:?}\tinstance: {}\tslot: {}\tused: {}", card_type, gift_instance, self.data[PCD_CARD_SLOT_OFFSET], self.data[PCD_CARD_USED_OFFSET] > 0)
+    }
+}
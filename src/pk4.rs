@@ -0,0 +1,448 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::fmt::{Display, Formatter};
+
+use crate::pcd::{CardType, first_str, PCD_PGT_LENGTH, put_str};
+use crate::pokestr::DecodeError;
+use crate::species::species_by_pokedex;
+
+/// Length of a party PK4 structure as embedded into a PGT.
+pub const PK4_LENGTH: usize = 0xec;
+/// Length of the box part which is shuffled and encrypted with the checksum.
+pub const PK4_BOX_LENGTH: usize = 0x88;
+pub const PK4_BLOCK_LENGTH: usize = 0x20;
+pub const PK4_BLOCKS: usize = 4;
+
+/// Offset of the PK4 within the PGT.
+pub const PGT_PK4_OFFSET: usize = 0x8;
+
+/// Attribute const offsets are absolute to the decrypted and unshuffled PK4 data
+pub const PK4_PID_OFFSET: usize = 0x0;
+pub const PK4_CHECKSUM_OFFSET: usize = 0x6;
+pub const PK4_DATA_OFFSET: usize = 0x8;
+
+pub const PK4_SPECIES_OFFSET: usize = 0x8;
+pub const PK4_HELD_ITEM_OFFSET: usize = 0xa;
+pub const PK4_OT_ID_OFFSET: usize = 0xc;
+pub const PK4_OT_SECRET_ID_OFFSET: usize = 0xe;
+pub const PK4_FRIENDSHIP_OFFSET: usize = 0x14;
+pub const PK4_ABILITY_OFFSET: usize = 0x15;
+pub const PK4_LANGUAGE_OFFSET: usize = 0x17;
+pub const PK4_EVS_OFFSET: usize = 0x18;
+pub const PK4_SINNOH_RIBBONS_OFFSET: usize = 0x24;
+
+pub const PK4_MOVES_OFFSET: usize = 0x28;
+pub const PK4_IVS_OFFSET: usize = 0x38;
+pub const PK4_HOENN_RIBBONS_OFFSET: usize = 0x3c;
+pub const PK4_ENCOUNTER_FLAGS_OFFSET: usize = 0x40;
+
+pub const PK4_NICKNAME_OFFSET: usize = 0x48;
+/// Length in u16 units inclusive termination
+pub const PK4_NICKNAME_MAX_LENGTH: usize = 0xb;
+pub const PK4_ORIGIN_GAME_OFFSET: usize = 0x5f;
+pub const PK4_SINNOH_RIBBONS_2_OFFSET: usize = 0x60;
+
+pub const PK4_OT_NAME_OFFSET: usize = 0x68;
+/// Length in u16 units inclusive termination
+pub const PK4_OT_NAME_MAX_LENGTH: usize = 0x8;
+pub const PK4_EGG_DATE_OFFSET: usize = 0x78;
+pub const PK4_MET_DATE_OFFSET: usize = 0x7b;
+pub const PK4_EGG_LOCATION_OFFSET: usize = 0x7e;
+pub const PK4_MET_LOCATION_OFFSET: usize = 0x80;
+pub const PK4_BALL_OFFSET: usize = 0x83;
+pub const PK4_MET_LEVEL_OFFSET: usize = 0x84;
+
+pub const PK4_PARTY_LEVEL_OFFSET: usize = 0x8c;
+
+/// Position of the blocks A, B, C and D within the shuffled data, indexed by the shuffle value.
+const BLOCK_POSITION: [[usize; PK4_BLOCKS]; 24] = [
+    [0, 1, 2, 3], [0, 1, 3, 2], [0, 2, 1, 3], [0, 3, 1, 2], [0, 2, 3, 1], [0, 3, 2, 1],
+    [1, 0, 2, 3], [1, 0, 3, 2], [2, 0, 1, 3], [3, 0, 1, 2], [2, 0, 3, 1], [3, 0, 2, 1],
+    [1, 2, 0, 3], [1, 3, 0, 2], [2, 1, 0, 3], [3, 1, 0, 2], [2, 3, 0, 1], [3, 2, 0, 1],
+    [1, 2, 3, 0], [1, 3, 2, 0], [2, 1, 3, 0], [3, 1, 2, 0], [2, 3, 1, 0], [3, 2, 1, 0],
+];
+
+pub const NATURES: [&str; 25] = [
+    "Hardy", "Lonely", "Brave", "Adamant", "Naughty",
+    "Bold", "Docile", "Relaxed", "Impish", "Lax",
+    "Timid", "Hasty", "Serious", "Jolly", "Naive",
+    "Modest", "Mild", "Quiet", "Bashful", "Rash",
+    "Calm", "Gentle", "Sassy", "Careful", "Quirky",
+];
+
+pub const BALLS: [&str; 17] = [
+    "None", "Master Ball", "Ultra Ball", "Great Ball", "Poké Ball", "Safari Ball", "Net Ball", "Dive Ball",
+    "Nest Ball", "Repeat Ball", "Timer Ball", "Luxury Ball", "Premier Ball", "Dusk Ball", "Heal Ball",
+    "Quick Ball", "Cherish Ball",
+];
+
+pub struct PK4<State> {
+    pub(crate) state: State,
+}
+
+/// The PK4 as stored in the PGT, shuffled and encrypted.
+pub struct Encrypted {
+    data: [u8; PK4_LENGTH],
+}
+
+/// The PK4 with decrypted and unshuffled blocks.
+pub struct Decrypted {
+    data: [u8; PK4_LENGTH],
+}
+
+pub struct Deserialized {
+    pub pid: u32,
+    pub species: u16,
+    pub held_item: u16,
+    pub ot_id: u16,
+    pub ot_secret_id: u16,
+    pub friendship: u8,
+    pub ability: u8,
+    pub language: u8,
+    pub evs: [u8; 6],
+    pub moves: [u16; 4],
+    /// Order: HP, Attack, Defense, Speed, Special Attack, Special Defense
+    pub ivs: [u8; 6],
+    pub is_egg: bool,
+    pub is_nicknamed: bool,
+    pub fateful_encounter: bool,
+    pub form: u8,
+    pub nickname: String,
+    pub origin_game: u8,
+    pub ot_name: String,
+    pub ot_female: bool,
+    /// Representation: (year since 2000, month of year, day of month)
+    pub egg_date: (u8, u8, u8),
+    /// Representation: (year since 2000, month of year, day of month)
+    pub met_date: (u8, u8, u8),
+    pub egg_location: u16,
+    pub met_location: u16,
+    pub ball: u8,
+    pub met_level: u8,
+    /// Current level in the party, which the games show
+    pub level: u8,
+    /// Representation: (sinnoh set 1, hoenn, sinnoh set 2)
+    pub ribbons: (u32, u32, u32),
+    pub checksum_valid: bool,
+    /// The decrypted data the fields are read from and written onto.
+    base: [u8; PK4_LENGTH],
+}
+
+/// Get the embedded PK4 from the pgt, if the card type carries a Pokémon.
+pub fn pgt_pokemon(pgt: &[u8; PCD_PGT_LENGTH], card_type: CardType) -> Option<PK4<Encrypted>> {
+    match card_type {
+        CardType::Pokemon | CardType::PokemonEgg | CardType::ManaphyEgg => {
+            let data = <[u8; PK4_LENGTH]>::try_from(&pgt[PGT_PK4_OFFSET..PGT_PK4_OFFSET + PK4_LENGTH]).unwrap();
+            Some(PK4 { state: Encrypted { data } })
+        }
+        _ => None
+    }
+}
+
+/// Replace the embedded PK4 within the pgt.
+pub fn put_pgt_pokemon(pgt: &mut [u8; PCD_PGT_LENGTH], pk: &PK4<Encrypted>) {
+    pgt[PGT_PK4_OFFSET..PGT_PK4_OFFSET + PK4_LENGTH].copy_from_slice(&pk.state.data);
+}
+
+/// Gen IV pseudo random number generator, used as key stream for the PK4 encryption.
+struct Prng {
+    seed: u32,
+}
+
+impl Iterator for Prng {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.seed = self.seed.wrapping_mul(0x41c64e6d).wrapping_add(0x6073);
+        Some((self.seed >> 16) as u16)
+    }
+}
+
+/// Apply the key stream of the [Prng] with `seed` to every u16 of `data`.
+/// Since it is a xor cipher, this encrypts as well as decrypts.
+fn crypt(data: &mut [u8], seed: u32) {
+    let prng = Prng { seed };
+    data.chunks_exact_mut(2).zip(prng).for_each(|(c, k)| {
+        let word = u16::from_le_bytes([c[0], c[1]]) ^ k;
+        c.copy_from_slice(&word.to_le_bytes());
+    });
+}
+
+fn pid(data: &[u8; PK4_LENGTH]) -> u32 {
+    u32::from_le_bytes(data[PK4_PID_OFFSET..PK4_PID_OFFSET + 4].try_into().unwrap())
+}
+
+fn stored_checksum(data: &[u8; PK4_LENGTH]) -> u16 {
+    u16::from_le_bytes([data[PK4_CHECKSUM_OFFSET], data[PK4_CHECKSUM_OFFSET + 1]])
+}
+
+fn shuffle_value(pid: u32) -> usize {
+    (((pid & 0x3e000) >> 0xd) % 24) as usize
+}
+
+impl PK4<Encrypted> {
+    /// Decrypts the blocks with the checksum seeded [Prng], the party stats with the pid seeded one,
+    /// and brings the blocks into the order A, B, C, D.
+    pub fn decrypt(&self) -> PK4<Decrypted> {
+        let mut data = self.state.data;
+        let pid = pid(&data);
+        let checksum = stored_checksum(&data);
+        crypt(&mut data[PK4_DATA_OFFSET..PK4_BOX_LENGTH], checksum as u32);
+        crypt(&mut data[PK4_BOX_LENGTH..], pid);
+
+        let shuffled = data;
+        let positions = BLOCK_POSITION[shuffle_value(pid)];
+        for (block, &position) in positions.iter().enumerate() {
+            let dest = PK4_DATA_OFFSET + block * PK4_BLOCK_LENGTH;
+            let src = PK4_DATA_OFFSET + position * PK4_BLOCK_LENGTH;
+            data[dest..dest + PK4_BLOCK_LENGTH].copy_from_slice(&shuffled[src..src + PK4_BLOCK_LENGTH]);
+        }
+        PK4 { state: Decrypted { data } }
+    }
+}
+
+impl PK4<Decrypted> {
+    /// Brings the blocks into the order given by the pid and encrypts them with the checksum seeded [Prng],
+    /// the party stats are encrypted with the pid seeded one.
+    /// The stored checksum is used as is, see [PK4::<Deserialized>::serialize] to repair it.
+    pub fn encrypt(&self) -> PK4<Encrypted> {
+        let plain = self.state.data;
+        let mut data = plain;
+        let pid = pid(&data);
+        let positions = BLOCK_POSITION[shuffle_value(pid)];
+        for (block, &position) in positions.iter().enumerate() {
+            let src = PK4_DATA_OFFSET + block * PK4_BLOCK_LENGTH;
+            let dest = PK4_DATA_OFFSET + position * PK4_BLOCK_LENGTH;
+            data[dest..dest + PK4_BLOCK_LENGTH].copy_from_slice(&plain[src..src + PK4_BLOCK_LENGTH]);
+        }
+        crypt(&mut data[PK4_DATA_OFFSET..PK4_BOX_LENGTH], stored_checksum(&plain) as u32);
+        crypt(&mut data[PK4_BOX_LENGTH..], pid);
+        PK4 { state: Encrypted { data } }
+    }
+
+    /// Calculates the checksum of the blocks, which is the wrapping sum of all u16 within them.
+    pub fn checksum(&self) -> u16 {
+        self.state.data[PK4_DATA_OFFSET..PK4_BOX_LENGTH].chunks_exact(2)
+            .fold(0u16, |a, c| a.wrapping_add(u16::from_le_bytes([c[0], c[1]])))
+    }
+
+    pub fn deserialize(&self) -> PK4<Deserialized> {
+        let data = &self.state.data;
+        let u16_at = |o: usize| u16::from_le_bytes([data[o], data[o + 1]]);
+        let u32_at = |o: usize| u32::from_le_bytes(data[o..o + 4].try_into().unwrap());
+        let ivs = u32_at(PK4_IVS_OFFSET);
+        let flags = data[PK4_ENCOUNTER_FLAGS_OFFSET];
+        let date_at = |o: usize| (data[o], data[o + 1], data[o + 2]);
+
+        let des = Deserialized {
+            pid: pid(data),
+            species: u16_at(PK4_SPECIES_OFFSET),
+            held_item: u16_at(PK4_HELD_ITEM_OFFSET),
+            ot_id: u16_at(PK4_OT_ID_OFFSET),
+            ot_secret_id: u16_at(PK4_OT_SECRET_ID_OFFSET),
+            friendship: data[PK4_FRIENDSHIP_OFFSET],
+            ability: data[PK4_ABILITY_OFFSET],
+            language: data[PK4_LANGUAGE_OFFSET],
+            evs: data[PK4_EVS_OFFSET..PK4_EVS_OFFSET + 6].try_into().unwrap(),
+            moves: [0, 1, 2, 3].map(|i| u16_at(PK4_MOVES_OFFSET + 2 * i)),
+            ivs: [0, 1, 2, 3, 4, 5].map(|i| ((ivs >> (5 * i)) & 0x1f) as u8),
+            is_egg: ivs & (1 << 30) > 0,
+            is_nicknamed: ivs & (1 << 31) > 0,
+            fateful_encounter: flags & 0x1 > 0,
+            form: flags >> 3,
+            nickname: (&first_str(&data[PK4_NICKNAME_OFFSET..], PK4_NICKNAME_MAX_LENGTH)).try_into().unwrap_or_else(|e: DecodeError| e.escaped),
+            origin_game: data[PK4_ORIGIN_GAME_OFFSET],
+            ot_name: (&first_str(&data[PK4_OT_NAME_OFFSET..], PK4_OT_NAME_MAX_LENGTH)).try_into().unwrap_or_else(|e: DecodeError| e.escaped),
+            ot_female: data[PK4_MET_LEVEL_OFFSET] & 0x80 > 0,
+            egg_date: date_at(PK4_EGG_DATE_OFFSET),
+            met_date: date_at(PK4_MET_DATE_OFFSET),
+            egg_location: u16_at(PK4_EGG_LOCATION_OFFSET),
+            met_location: u16_at(PK4_MET_LOCATION_OFFSET),
+            ball: data[PK4_BALL_OFFSET],
+            met_level: data[PK4_MET_LEVEL_OFFSET] & 0x7f,
+            level: data[PK4_PARTY_LEVEL_OFFSET],
+            ribbons: (u32_at(PK4_SINNOH_RIBBONS_OFFSET), u32_at(PK4_HOENN_RIBBONS_OFFSET), u32_at(PK4_SINNOH_RIBBONS_2_OFFSET)),
+            checksum_valid: self.checksum() == stored_checksum(data),
+            base: *data,
+        };
+        PK4 { state: des }
+    }
+}
+
+impl PK4<Deserialized> {
+    /// The nature is determined by the pid.
+    pub fn nature(&self) -> u8 {
+        (self.state.pid % NATURES.len() as u32) as u8
+    }
+
+    /// Changes the pid as little as possible so that it results in the given nature.
+    /// Note that gender, ability and shininess depend on the pid as well and may change.
+    pub fn set_nature(&mut self, nature: u8) {
+        let natures = NATURES.len() as u32;
+        let nature = nature as u32 % natures;
+        let base = self.state.pid - self.state.pid % natures;
+        self.state.pid = base.checked_add(nature).unwrap_or_else(|| base - natures + nature);
+    }
+
+    /// Changes the species, a Pokémon without nickname is renamed to the species name like the games do.
+    pub fn set_species(&mut self, species: u16) {
+        self.state.species = species;
+        if let (false, Some(name)) = (self.state.is_nicknamed, species_by_pokedex(species as usize)) {
+            self.state.nickname = name.to_uppercase();
+        }
+    }
+
+    /// Like [crate::pcd::PCD::serialize], but also repairs the checksum of the Pokémon.
+    pub fn serialize(&self) -> PK4<Decrypted> {
+        let pk = &self.state;
+        let original = PK4 { state: Decrypted { data: pk.base } }.deserialize().state;
+        let mut data = pk.base;
+        let put_u16 = |data: &mut [u8; PK4_LENGTH], o: usize, v: u16| data[o..o + 2].copy_from_slice(&v.to_le_bytes());
+        let put_u32 = |data: &mut [u8; PK4_LENGTH], o: usize, v: u32| data[o..o + 4].copy_from_slice(&v.to_le_bytes());
+
+        put_u32(&mut data, PK4_PID_OFFSET, pk.pid);
+        put_u16(&mut data, PK4_SPECIES_OFFSET, pk.species);
+        put_u16(&mut data, PK4_HELD_ITEM_OFFSET, pk.held_item);
+        put_u16(&mut data, PK4_OT_ID_OFFSET, pk.ot_id);
+        put_u16(&mut data, PK4_OT_SECRET_ID_OFFSET, pk.ot_secret_id);
+        data[PK4_FRIENDSHIP_OFFSET] = pk.friendship;
+        data[PK4_ABILITY_OFFSET] = pk.ability;
+        data[PK4_LANGUAGE_OFFSET] = pk.language;
+        data[PK4_EVS_OFFSET..PK4_EVS_OFFSET + 6].copy_from_slice(&pk.evs);
+        put_u32(&mut data, PK4_SINNOH_RIBBONS_OFFSET, pk.ribbons.0);
+
+        pk.moves.iter().enumerate().for_each(|(i, &m)| put_u16(&mut data, PK4_MOVES_OFFSET + 2 * i, m));
+        let ivs = pk.ivs.iter().enumerate().fold(0u32, |a, (i, &iv)| a | ((iv as u32 & 0x1f) << (5 * i)))
+            | (pk.is_egg as u32) << 30 | (pk.is_nicknamed as u32) << 31;
+        put_u32(&mut data, PK4_IVS_OFFSET, ivs);
+        put_u32(&mut data, PK4_HOENN_RIBBONS_OFFSET, pk.ribbons.1);
+        data[PK4_ENCOUNTER_FLAGS_OFFSET] = pk.fateful_encounter as u8 | data[PK4_ENCOUNTER_FLAGS_OFFSET] & 0x6 | pk.form << 3;
+
+        if pk.nickname != original.nickname {
+            put_str(&mut data[PK4_NICKNAME_OFFSET..], &pk.nickname, PK4_NICKNAME_MAX_LENGTH);
+        }
+        data[PK4_ORIGIN_GAME_OFFSET] = pk.origin_game;
+        put_u32(&mut data, PK4_SINNOH_RIBBONS_2_OFFSET, pk.ribbons.2);
+
+        if pk.ot_name != original.ot_name {
+            put_str(&mut data[PK4_OT_NAME_OFFSET..], &pk.ot_name, PK4_OT_NAME_MAX_LENGTH);
+        }
+        data[PK4_EGG_DATE_OFFSET..PK4_EGG_DATE_OFFSET + 3].copy_from_slice(&[pk.egg_date.0, pk.egg_date.1, pk.egg_date.2]);
+        data[PK4_MET_DATE_OFFSET..PK4_MET_DATE_OFFSET + 3].copy_from_slice(&[pk.met_date.0, pk.met_date.1, pk.met_date.2]);
+        put_u16(&mut data, PK4_EGG_LOCATION_OFFSET, pk.egg_location);
+        put_u16(&mut data, PK4_MET_LOCATION_OFFSET, pk.met_location);
+        data[PK4_BALL_OFFSET] = pk.ball;
+        data[PK4_MET_LEVEL_OFFSET] = pk.met_level & 0x7f | (pk.ot_female as u8) << 7;
+        data[PK4_PARTY_LEVEL_OFFSET] = pk.level;
+
+        let mut decrypted = PK4 { state: Decrypted { data } };
+        let checksum = decrypted.checksum();
+        put_u16(&mut decrypted.state.data, PK4_CHECKSUM_OFFSET, checksum);
+        decrypted
+    }
+}
+
+/// Name of the language the Pokémon originates from.
+pub fn language_name(language: u8) -> &'static str {
+    match language {
+        1 => "Japanese",
+        2 => "English",
+        3 => "French",
+        4 => "Italian",
+        5 => "German",
+        7 => "Spanish",
+        8 => "Korean",
+        _ => "Unknown",
+    }
+}
+
+impl Display for PK4<Deserialized> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let pk = &self.state;
+        let (egg_year, egg_month, egg_day) = pk.egg_date;
+        let (met_year, met_month, met_day) = pk.met_date;
+        write!(f, "pokemon: {}({})\tlevel: {}\tnickname: {}{}{}\n\
+        OT: {} ({:05}/{:05}){}\tlanguage: {}({})\torigin game: {}\tfriendship: {}\n\
+        nature: {}\tability: {}\tform: {}\theld item: {}\tball: {}({})\n\
+        moves: {}, {}, {}, {}\n\
+        IVs: {}/{}/{}/{}/{}/{}\tEVs: {}/{}/{}/{}/{}/{}\n\
+        ribbons: {:08x} {:08x} {:08x}\n\
+        met: location {} at level {} on {}-{:02}-{:02}\tegg: location {} on {}-{:02}-{:02}{}\n{}",
+               species_by_pokedex(pk.species as usize).unwrap_or("None"), pk.species, pk.level,
+               pk.nickname, if pk.is_nicknamed { "" } else { " (not nicknamed)" }, if pk.is_egg { "\tegg" } else { "" },
+               pk.ot_name, pk.ot_id, pk.ot_secret_id, if pk.ot_female { " female" } else { "" }, language_name(pk.language), pk.language, pk.origin_game, pk.friendship,
+               NATURES[self.nature() as usize], pk.ability, pk.form, pk.held_item, BALLS.get(pk.ball as usize).unwrap_or(&"Unknown"), pk.ball,
+               pk.moves[0], pk.moves[1], pk.moves[2], pk.moves[3],
+               pk.ivs[0], pk.ivs[1], pk.ivs[2], pk.ivs[3], pk.ivs[4], pk.ivs[5],
+               pk.evs[0], pk.evs[1], pk.evs[2], pk.evs[3], pk.evs[4], pk.evs[5],
+               pk.ribbons.0, pk.ribbons.1, pk.ribbons.2,
+               pk.met_location, pk.met_level, 2000 + met_year as u16, met_month, met_day, pk.egg_location, 2000 + egg_year as u16, egg_month, egg_day,
+               if pk.fateful_encounter { "\tfateful encounter" } else { "" },
+               if pk.checksum_valid { "" } else { "warning: the checksum of the pokemon does not match, the data is likely corrupted\n" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pk4_decrypt_unshuffle() {
+        let mut plain = [0u8; PK4_LENGTH];
+        let pid: u32 = 0x0001_e000; // shuffle value 15: blocks stored as C, B, D, A
+        plain[PK4_PID_OFFSET..PK4_PID_OFFSET + 4].copy_from_slice(&pid.to_le_bytes());
+        plain[PK4_SPECIES_OFFSET..PK4_SPECIES_OFFSET + 2].copy_from_slice(&490u16.to_le_bytes());
+        plain[PK4_MOVES_OFFSET..PK4_MOVES_OFFSET + 2].copy_from_slice(&294u16.to_le_bytes());
+        plain[PK4_MET_LEVEL_OFFSET] = 0x80 | 50;
+        let decrypted = PK4 { state: Decrypted { data: plain } };
+        let checksum = decrypted.checksum();
+        plain[PK4_CHECKSUM_OFFSET..PK4_CHECKSUM_OFFSET + 2].copy_from_slice(&checksum.to_le_bytes());
+
+        let mut stored = plain;
+        for (block, &position) in BLOCK_POSITION[shuffle_value(pid)].iter().enumerate() {
+            let src = PK4_DATA_OFFSET + block * PK4_BLOCK_LENGTH;
+            let dest = PK4_DATA_OFFSET + position * PK4_BLOCK_LENGTH;
+            stored[dest..dest + PK4_BLOCK_LENGTH].copy_from_slice(&plain[src..src + PK4_BLOCK_LENGTH]);
+        }
+        crypt(&mut stored[PK4_DATA_OFFSET..PK4_BOX_LENGTH], checksum as u32);
+        crypt(&mut stored[PK4_BOX_LENGTH..], pid);
+
+        let encrypted = PK4 { state: Decrypted { data: plain } }.encrypt();
+        assert_eq!(stored, encrypted.state.data);
+
+        let pk = PK4 { state: Encrypted { data: stored } }.decrypt();
+        assert_eq!(plain, pk.state.data);
+
+        let des = pk.deserialize();
+        assert_eq!(490, des.state.species);
+        assert_eq!([294, 0, 0, 0], des.state.moves);
+        assert_eq!(50, des.state.met_level);
+        assert!(des.state.ot_female);
+        assert!(des.state.checksum_valid);
+    }
+
+    #[test]
+    fn pk4_edit_round_trip() {
+        let mut plain = [0u8; PK4_LENGTH];
+        plain[PK4_PID_OFFSET..PK4_PID_OFFSET + 4].copy_from_slice(&0x1234_5678u32.to_le_bytes());
+        plain[PK4_NICKNAME_OFFSET..PK4_NICKNAME_OFFSET + 2].copy_from_slice(&0xffffu16.to_le_bytes());
+        let unchanged = PK4 { state: Decrypted { data: plain } }.deserialize().serialize();
+        assert_eq!(&plain[PK4_DATA_OFFSET..], &unchanged.state.data[PK4_DATA_OFFSET..]);
+
+        let mut des = unchanged.encrypt().decrypt().deserialize();
+        des.set_species(151);
+        des.state.ivs = [31, 30, 29, 28, 27, 26];
+        des.state.met_level = 10;
+        des.state.level = 12;
+        des.set_nature(3);
+        let pk = des.serialize().encrypt().decrypt().deserialize();
+        assert!(pk.state.checksum_valid);
+        assert_eq!(151, pk.state.species);
+        assert_eq!("MEW", pk.state.nickname);
+        assert_eq!([31, 30, 29, 28, 27, 26], pk.state.ivs);
+        assert_eq!(10, pk.state.met_level);
+        assert_eq!(3, pk.nature());
+        assert_eq!(12, pk.state.level);
+        assert_eq!(12, pk.state.base[PK4_PARTY_LEVEL_OFFSET]);
+    }
+}
@@ -0,0 +1,198 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::beacon::BeaconFrameGenerator;
+use crate::error::err_reason;
+use crate::GGID;
+
+/// A card of a playlist with the region it is distributed in, it is distributed in every region if none is given.
+#[derive(Clone)]
+pub struct PlaylistEntry {
+    pub pcd: PathBuf,
+    pub region: Option<GGID>,
+}
+
+/// When to switch to the next card of a [Playlist].
+#[derive(Copy, Clone, Debug)]
+pub enum Dwell {
+    /// After the time in µs has passed, the current round is completed nonetheless.
+    Time(u64),
+    /// After the amount of complete rounds.
+    Rounds(u64),
+}
+
+impl Display for Dwell {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Dwell::Time(t) => write!(f, "{} s", t / 1_000_000),
+            Dwell::Rounds(r) => write!(f, "{} rounds", r),
+        }
+    }
+}
+
+/// Reads a playlist file.
+///
+/// Each line holds the path to a PCD file, optionally followed by the region separated by whitespace.
+/// Empty lines and lines starting with `#` are skipped, relative paths are relative to the playlist file.
+///
+/// # Arguments
+///
+/// * `playlist` - A [PathBuf] representing the path to the playlist file.
+///
+/// # Returns
+///
+/// Returns the entries of the playlist in order.
+///
+pub fn read_playlist(playlist: &Path) -> Result<Vec<PlaylistEntry>, Box<dyn Error>> {
+    let content = fs::read_to_string(playlist).map_err(|e| err_reason("Unable to read playlist file", e))?;
+    let base = playlist.parent().unwrap_or(Path::new(""));
+    content.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|l| {
+            let (pcd, region) = match l.rsplit_once(char::is_whitespace) {
+                Some((pcd, region)) => (pcd.trim_end(), Some(GGID::from_str(region, true).map_err(|_| format!("Invalid region '{}' in playlist line '{}'", region, l))?)),
+                None => (l, None),
+            };
+            Ok(PlaylistEntry { pcd: base.join(pcd), region })
+        })
+        .collect()
+}
+
+/// A card of a [Playlist] with its progress.
+pub struct Card {
+    pub name: String,
+    pub region: GGID,
+    pub generator: BeaconFrameGenerator,
+    /// Complete rounds distributed so far.
+    pub rounds: u64,
+}
+
+impl Card {
+    pub fn log_progress(&self) {
+        eprintln!("Card '{}' ({}) distributed {} rounds in total", self.name, self.region, self.rounds);
+    }
+}
+
+/// Cycles through the frames of multiple cards, switching to the next card after the dwell.
+pub struct Playlist {
+    cards: Vec<Card>,
+    current: usize,
+    dwell: Dwell,
+    /// The interval between two frames in µs.
+    interval: u64,
+    /// Frames of the current card since switching to it.
+    frames: u64,
+    /// Frames of all cards.
+    total_frames: u64,
+}
+
+impl Playlist {
+    pub fn new(cards: Vec<Card>, dwell: Dwell, interval: u64) -> Self {
+        Playlist { cards, current: 0, dwell, interval, frames: 0, total_frames: 0 }
+    }
+
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    /// The number of frames of one complete distribution of the current card.
+    pub fn round_length(&self) -> usize {
+        self.cards[self.current].generator.round_length()
+    }
+
+    fn dwelled(&self) -> bool {
+        let round_length = self.round_length() as u64;
+        if !self.frames.is_multiple_of(round_length) {
+            return false;
+        }
+        match self.dwell {
+            Dwell::Time(t) => self.frames * self.interval >= t,
+            Dwell::Rounds(r) => self.frames / round_length >= r,
+        }
+    }
+}
+
+impl Iterator for Playlist {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.cards.len() > 1 && self.frames > 0 && self.dwelled() {
+            self.cards[self.current].log_progress();
+            self.current = (self.current + 1) % self.cards.len();
+            self.frames = 0;
+            let tsf = self.total_frames * self.interval;
+            self.cards[self.current].generator.set_tsf(tsf);
+        }
+        let round_length = self.round_length() as u64;
+        let (count, current) = (self.cards.len(), self.current);
+        let card = &mut self.cards[current];
+        if self.frames == 0 && count > 1 {
+            eprintln!("Distributing card {}/{} '{}' for region '{}'...", current + 1, count, card.name, card.region);
+        }
+        self.frames += 1;
+        self.total_frames += 1;
+        if self.frames.is_multiple_of(round_length) {
+            card.rounds += 1;
+        }
+        card.generator.next()
+    }
+}
+
+/// Interleaves the frames of the playlists of multiple regions, one frame of each region after another.
+pub struct Interleaved {
+    playlists: Vec<Playlist>,
+    next: usize,
+}
+
+impl Interleaved {
+    pub fn new(playlists: Vec<Playlist>) -> Self {
+        Interleaved { playlists, next: 0 }
+    }
+
+    pub fn playlists(&self) -> &[Playlist] {
+        &self.playlists
+    }
+
+    /// The number of frames of one complete distribution of the current card of every region.
+    pub fn round_length(&self) -> usize {
+        self.playlists.iter().map(Playlist::round_length).sum()
+    }
+}
+
+impl Iterator for Interleaved {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let frame = self.playlists[self.next].next();
+        self.next = (self.next + 1) % self.playlists.len();
+        frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon::test_generator;
+
+    fn card(name: &str, region: GGID) -> Card {
+        Card { name: name.to_string(), region, generator: test_generator(region), rounds: 0 }
+    }
+
+    #[test]
+    fn switch_after_dwell() {
+        let mut playlist = Playlist::new(vec![card("a", GGID::English), card("b", GGID::German)], Dwell::Rounds(2), 10240);
+        playlist.by_ref().take(50).for_each(drop);
+        assert_eq!(vec![3, 2], playlist.cards().iter().map(|c| c.rounds).collect::<Vec<u64>>());
+
+        let mut playlist = Playlist::new(vec![card("a", GGID::English), card("b", GGID::German)], Dwell::Time(150_000), 10240);
+        playlist.by_ref().take(50).for_each(drop);
+        assert_eq!(vec![3, 2], playlist.cards().iter().map(|c| c.rounds).collect::<Vec<u64>>());
+    }
+}
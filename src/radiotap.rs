@@ -0,0 +1,253 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use clap::ValueEnum;
+
+/// The kind of radiotap header preceding the beacon frames.
+#[derive(Copy, Clone, Debug, PartialEq, ValueEnum)]
+pub enum Radiotap {
+    /// A minimal transmit header with TX flags, data rate and optional TX power
+    Minimal,
+    /// The header recorded from a received distribution
+    Legacy,
+}
+
+/// A radiotap header and where to patch the timestamps of each frame into.
+pub struct RadioHead {
+    data: Vec<u8>,
+    /// Offsets of the u64 timestamps in µs within `data`.
+    timestamp_offsets: &'static [usize],
+}
+
+impl RadioHead {
+    /// Creates the radiotap header of the given kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The [Radiotap] kind.
+    /// * `channel` - The Wi-Fi channel from 1 to 14.
+    /// * `tx_power` - The transmit power in dBm, only supported by [Radiotap::Minimal].
+    ///
+    pub fn new(kind: Radiotap, channel: u8, tx_power: Option<i8>) -> Result<Self, String> {
+        match kind {
+            Radiotap::Minimal => {
+                let mut builder = RadiotapBuilder::new().rate(DATA_RATE).channel(channel).no_ack().fcs();
+                if let Some(p) = tx_power {
+                    builder = builder.tx_power(p);
+                }
+                Ok(RadioHead { data: builder.build(), timestamp_offsets: &[] })
+            }
+            Radiotap::Legacy => {
+                if tx_power.is_some() {
+                    return Err("The TX power can only be set for the minimal radiotap header".to_string());
+                }
+                let mut data = LEGACY_RADIO_HEAD.to_vec();
+                data[LEGACY_FREQUENCY_OFFSET..LEGACY_FREQUENCY_OFFSET + 2].copy_from_slice(&channel_frequency(channel).to_le_bytes());
+                Ok(RadioHead { data, timestamp_offsets: &[LEGACY_MAC_TIMESTAMP_OFFSET, LEGACY_TIMESTAMP_OFFSET] })
+            }
+        }
+    }
+
+    /// The header with all timestamps set to `tsf`.
+    pub fn with_timestamp(&self, tsf: u64) -> Vec<u8> {
+        let mut data = self.data.clone();
+        for &o in self.timestamp_offsets {
+            data[o..o + 8].copy_from_slice(&tsf.to_le_bytes());
+        }
+        data
+    }
+}
+
+/// Builds a radiotap header for transmission.
+///
+/// The fields are written in the order of their present bits with the alignment required by radiotap.
+pub struct RadiotapBuilder {
+    flags: u8,
+    rate: Option<u8>,
+    channel: Option<u8>,
+    tx_power: Option<i8>,
+    tx_flags: u16,
+}
+
+const PRESENT_TSFT: u32 = 1 << 0;
+const PRESENT_FLAGS: u32 = 1 << 1;
+const PRESENT_RATE: u32 = 1 << 2;
+const PRESENT_CHANNEL: u32 = 1 << 3;
+const PRESENT_DBM_TX_POWER: u32 = 1 << 10;
+const PRESENT_TX_FLAGS: u32 = 1 << 15;
+
+/// The frame includes the FCS at its end.
+const FLAG_FCS: u8 = 0x10;
+/// The frame is not expected to be acknowledged.
+const TX_FLAG_NO_ACK: u16 = 0x8;
+/// CCK in the 2 GHz band.
+const CHANNEL_FLAGS: u16 = 0x00a0;
+/// 2 Mbps in 500 kbps units.
+const DATA_RATE: u8 = 0x04;
+
+impl RadiotapBuilder {
+    pub fn new() -> Self {
+        RadiotapBuilder { flags: 0, rate: None, channel: None, tx_power: None, tx_flags: 0 }
+    }
+
+    /// Data rate in 500 kbps units.
+    pub fn rate(mut self, rate: u8) -> Self {
+        self.rate = Some(rate);
+        self
+    }
+
+    /// Wi-Fi channel from 1 to 14.
+    pub fn channel(mut self, channel: u8) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// Transmit power in dBm.
+    pub fn tx_power(mut self, tx_power: i8) -> Self {
+        self.tx_power = Some(tx_power);
+        self
+    }
+
+    /// Do not wait for an acknowledgement, which is never sent for broadcasts anyway.
+    pub fn no_ack(mut self) -> Self {
+        self.tx_flags |= TX_FLAG_NO_ACK;
+        self
+    }
+
+    /// The frames end with the FCS.
+    pub fn fcs(mut self) -> Self {
+        self.flags |= FLAG_FCS;
+        self
+    }
+
+    pub fn build(&self) -> Vec<u8> {
+        let mut present = 0;
+        let mut fields: Vec<u8> = vec![];
+        // fields start after the 8 bytes of version, pad, length and present flags
+        let align = |fields: &mut Vec<u8>, alignment: usize| {
+            while !(8 + fields.len()).is_multiple_of(alignment) {
+                fields.push(0);
+            }
+        };
+        if self.flags > 0 {
+            present |= PRESENT_FLAGS;
+            fields.push(self.flags);
+        }
+        if let Some(r) = self.rate {
+            present |= PRESENT_RATE;
+            fields.push(r);
+        }
+        if let Some(c) = self.channel {
+            present |= PRESENT_CHANNEL;
+            align(&mut fields, 2);
+            fields.extend_from_slice(&channel_frequency(c).to_le_bytes());
+            fields.extend_from_slice(&CHANNEL_FLAGS.to_le_bytes());
+        }
+        if let Some(p) = self.tx_power {
+            present |= PRESENT_DBM_TX_POWER;
+            fields.push(p as u8);
+        }
+        if self.tx_flags > 0 {
+            present |= PRESENT_TX_FLAGS;
+            align(&mut fields, 2);
+            fields.extend_from_slice(&self.tx_flags.to_le_bytes());
+        }
+        [
+            [0x00, 0x00].as_slice(), // rev, pad
+            &((8 + fields.len()) as u16).to_le_bytes(), // header length
+            &present.to_le_bytes(),
+            &fields,
+        ].concat()
+    }
+}
+
+/// The center frequency of a 2.4 GHz Wi-Fi channel in MHz.
+pub fn channel_frequency(channel: u8) -> u16 {
+    match channel {
+        14 => 2484,
+        c => 2407 + 5 * c as u16,
+    }
+}
+
+/// Splits a received frame into the radiotap header and the 802.11 frame.
+///
+/// # Returns
+///
+/// Returns the 802.11 frame and whether it ends with the FCS according to the flags of the radiotap header,
+/// or [None] if the radiotap header is truncated.
+///
+pub fn strip(frame: &[u8]) -> Option<(&[u8], bool)> {
+    let length = u16::from_le_bytes([*frame.get(2)?, *frame.get(3)?]) as usize;
+    let head = frame.get(..length)?;
+    // the present flags are extended by further words while bit 31 is set
+    let mut offset = 4;
+    let mut present = u32::from_le_bytes(head.get(offset..offset + 4)?.try_into().unwrap());
+    let first_present = present;
+    while present & 1 << 31 != 0 {
+        offset += 4;
+        present = u32::from_le_bytes(head.get(offset..offset + 4)?.try_into().unwrap());
+    }
+    offset += 4;
+    if first_present & PRESENT_TSFT != 0 {
+        offset = offset.next_multiple_of(8) + 8;
+    }
+    let fcs = first_present & PRESENT_FLAGS != 0 && head.get(offset)? & FLAG_FCS != 0;
+    Some((&frame[length..], fcs))
+}
+
+/// Offset of the MAC timestamp within [LEGACY_RADIO_HEAD].
+const LEGACY_MAC_TIMESTAMP_OFFSET: usize = 16;
+/// Offset of the channel frequency within [LEGACY_RADIO_HEAD].
+const LEGACY_FREQUENCY_OFFSET: usize = 26;
+/// Offset of the timestamp of the timestamp information within [LEGACY_RADIO_HEAD].
+const LEGACY_TIMESTAMP_OFFSET: usize = 40;
+
+const LEGACY_RADIO_HEAD: [u8; 56] = [
+    0x00, 0x00, // rev, pad
+    0x38, 0x00, // header length
+    0x2f, 0x40, 0x40, 0xa0, 0x20, 0x08, 0x00, 0xa0, 0x20, 0x08, 0x00, 0x00, // present flags
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // MAC timestamp, patched per frame
+    0x12, // flags
+    0x04, // data rate
+    0x8a, 0x09, // channel frequency
+    0xa0, 0x00, // channel flags
+    0xbd, // antenna signal
+    0x00, // ?
+    0x00, 0x00, // rx flags
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // ?
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x16, 0x00, 0x11, 0x03, // timestamp information, timestamp patched per frame
+    0xbc, // antenna signal
+    0x00, // antenna
+    0xbd, // antenna signal
+    0x01 // antenna
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn minimal_header() {
+        let head = RadiotapBuilder::new().rate(DATA_RATE).channel(7).no_ack().fcs().tx_power(20).build();
+        assert_eq!(vec![
+            0x00, 0x00, 0x12, 0x00, // rev, pad, header length
+            0x0e, 0x84, 0x00, 0x00, // present flags
+            0x10, // flags
+            0x04, // data rate
+            0x8a, 0x09, 0xa0, 0x00, // channel
+            0x14, // dbm tx power
+            0x00, // alignment
+            0x08, 0x00, // tx flags
+        ], head);
+    }
+
+    #[test]
+    fn strip_headers() {
+        let frame = [0x80, 0x00];
+        let minimal = [RadiotapBuilder::new().rate(DATA_RATE).fcs().build().as_slice(), &frame].concat();
+        assert_eq!(Some((frame.as_slice(), true)), strip(&minimal));
+        let legacy = [RadioHead::new(Radiotap::Legacy, 7, None).unwrap().with_timestamp(0).as_slice(), &frame].concat();
+        assert_eq!(Some((frame.as_slice(), true)), strip(&legacy));
+        let without_flags = [RadiotapBuilder::new().rate(DATA_RATE).build().as_slice(), &frame].concat();
+        assert_eq!(Some((frame.as_slice(), false)), strip(&without_flags));
+    }
+}
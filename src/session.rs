@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::LimitArgs;
+use crate::pacing::{Pacer, PacingStats};
+use crate::playlist::Interleaved;
+use crate::sink::FrameSink;
+
+/// Consecutive send errors after which the distribution is aborted.
+const MAX_CONSECUTIVE_ERRORS: u64 = 100;
+
+/// Statistics of a distribution run.
+#[derive(Default)]
+pub struct Summary {
+    pub frames: u64,
+    /// Complete rounds of every region.
+    pub rounds: u64,
+    pub errors: u64,
+    pub elapsed: Duration,
+    /// Timing measurements, if the frames were paced.
+    pub pacing: Option<PacingStats>,
+}
+
+impl Display for Summary {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rate = if self.elapsed.is_zero() { 0.0 } else { self.frames as f64 / self.elapsed.as_secs_f64() };
+        write!(f, "frames sent: {}\trounds: {}\tsend errors: {}\tduration: {:.1} s\tframe rate: {:.1} frames/s",
+               self.frames, self.rounds, self.errors, self.elapsed.as_secs_f64(), rate)?;
+        if let Some(p) = &self.pacing {
+            write!(f, "\n{}", p)?;
+        }
+        Ok(())
+    }
+}
+
+impl LimitArgs {
+    /// Whether any limit is reached after `rounds` in `elapsed` time since `start`.
+    fn reached(&self, rounds: u64, elapsed: Duration, start: SystemTime) -> bool {
+        self.rounds.is_some_and(|r| rounds >= r)
+            || self.duration.is_some_and(|d| elapsed >= Duration::from_secs(d))
+            || self.until.is_some_and(|u| start + elapsed >= u)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rounds.is_none() && self.duration.is_none() && self.until.is_none()
+    }
+}
+
+/// The flag of the handler, which can only be installed once per process.
+static SHUTDOWN: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+
+/// Installs a handler for SIGINT and SIGTERM, the returned flag is set once a signal was received.
+pub fn shutdown_flag() -> Result<Arc<AtomicBool>, Box<dyn Error>> {
+    let mut shutdown = SHUTDOWN.lock().map_err(|_| "The shutdown flag is poisoned")?;
+    if let Some(flag) = shutdown.as_ref() {
+        return Ok(flag.clone());
+    }
+    let flag = Arc::new(AtomicBool::new(false));
+    let handler_flag = flag.clone();
+    ctrlc::set_handler(move || handler_flag.store(true, Ordering::SeqCst))?;
+    *shutdown = Some(flag.clone());
+    Ok(flag)
+}
+
+/// Hands the frames to the sink until a limit is reached or a shutdown is requested.
+///
+/// # Arguments
+///
+/// * `interleaved` - The frames to distribute.
+/// * `limits` - The [LimitArgs] ending the run.
+/// * `interval` - The time between two frames in µs.
+/// * `pacing` - The [Pacer] waiting for the deadline of each frame, otherwise the time only advances virtually.
+/// * `sink` - The [FrameSink] receiving each frame with its offset from the start in µs.
+///
+/// # Returns
+///
+/// Returns the [Summary] of the run, or an error if sending failed repeatedly.
+///
+pub fn run(interleaved: &mut Interleaved, limits: &LimitArgs, interval: u64, mut pacing: Option<Pacer>, sink: &mut dyn FrameSink) -> Result<Summary, Box<dyn Error>> {
+    let shutdown = shutdown_flag()?;
+    let round_length = interleaved.round_length() as u64;
+    let start_time = SystemTime::now();
+    let start = Instant::now();
+    let mut summary = Summary::default();
+    let mut handled = 0;
+    let mut consecutive_errors = 0;
+    loop {
+        let offset = handled * interval;
+        summary.elapsed = if pacing.is_some() { start.elapsed() } else { Duration::from_micros(offset) };
+        summary.rounds = handled / round_length;
+        if shutdown.load(Ordering::SeqCst) || limits.reached(summary.rounds, summary.elapsed, start_time) {
+            break;
+        }
+        let Some(frame) = interleaved.next() else { break };
+        if let Some(p) = &mut pacing {
+            p.wait();
+        }
+        handled += 1;
+        match sink.send(&frame, offset) {
+            Ok(()) => {
+                summary.frames += 1;
+                consecutive_errors = 0;
+            }
+            Err(e) => {
+                if summary.errors == 0 {
+                    eprintln!("Warning: unable to send frame: {}", e);
+                }
+                summary.errors += 1;
+                consecutive_errors += 1;
+                if consecutive_errors >= MAX_CONSECUTIVE_ERRORS {
+                    summary.pacing = pacing.map(Pacer::into_stats);
+                    eprintln!("{}", summary);
+                    return Err(format!("Aborted after {} consecutive send errors, last error: {}", consecutive_errors, e).into());
+                }
+            }
+        }
+    }
+    summary.pacing = pacing.map(Pacer::into_stats);
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon::test_generator;
+    use crate::GGID;
+    use crate::playlist::{Card, Dwell, Playlist};
+    use crate::sink::MemorySink;
+
+    fn playlist(region: GGID) -> Playlist {
+        Playlist::new(vec![Card { name: "a".to_string(), region, generator: test_generator(region), rounds: 0 }], Dwell::Rounds(1), 10240)
+    }
+
+    #[test]
+    fn rounds_limit() {
+        let mut interleaved = Interleaved::new(vec![playlist(GGID::English), playlist(GGID::French)]);
+        let limits = LimitArgs { duration: None, rounds: Some(2), until: None };
+        let mut sink = MemorySink::default();
+        let summary = run(&mut interleaved, &limits, 5120, None, &mut sink).unwrap();
+        assert_eq!(2, summary.rounds);
+        assert_eq!(40, summary.frames);
+        assert_eq!(Duration::from_micros(40 * 5120), summary.elapsed);
+        assert!(sink.frames.iter().enumerate().all(|(i, (offset, _))| *offset == i as u64 * 5120));
+    }
+}
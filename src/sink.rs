@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use pcap::{Active, Capture, Linktype, Packet, PacketHeader, Savefile};
+
+use crate::error::err_reason;
+use crate::MacAddress;
+
+/// A destination of the generated frames.
+pub trait FrameSink {
+    /// Hands over a frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The frame including the radiotap header.
+    /// * `offset` - The time since the start of the distribution in µs.
+    ///
+    fn send(&mut self, frame: &[u8], offset: u64) -> Result<(), Box<dyn Error>>;
+
+    /// Whether the frames are sent in real time and have to be paced, otherwise the time only advances virtually.
+    fn is_live(&self) -> bool {
+        false
+    }
+
+    /// Writes out buffered frames once the distribution ended.
+    fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Sends the frames on a network device.
+pub struct LiveSink {
+    cap: Capture<Active>,
+}
+
+impl LiveSink {
+    /// Opens the network device, which has to be in monitor mode.
+    pub fn open(device: &str, address: MacAddress) -> Result<Self, Box<dyn Error>> {
+        let broadcast_addr: MacAddress = [0xff, 0xff, 0xff, 0xff, 0xff, 0xff];
+        eprintln!("Use device '{}' with ethernet address '{:02x?}' and broadcast address '{:02x?}'", device, address, broadcast_addr);
+        Ok(LiveSink { cap: Capture::from_device(device)?.open()? })
+    }
+}
+
+impl FrameSink for LiveSink {
+    fn send(&mut self, frame: &[u8], _: u64) -> Result<(), Box<dyn Error>> {
+        Ok(self.cap.sendpacket(frame)?)
+    }
+
+    fn is_live(&self) -> bool {
+        true
+    }
+}
+
+/// Writes the frames to a pcap savefile with the timestamps they would have been sent at.
+pub struct SavefileSink {
+    out: Savefile,
+    /// The time of the first frame since the unix epoch.
+    start: Duration,
+}
+
+impl SavefileSink {
+    pub fn create(savefile: PathBuf) -> Result<Self, Box<dyn Error>> {
+        eprintln!("Writing frames to '{}'", savefile.as_path().display());
+        let out = Capture::dead(Linktype::IEEE802_11_RADIOTAP)?.savefile(savefile).map_err(|e| err_reason("Unable to create savefile", e))?;
+        Ok(SavefileSink { out, start: SystemTime::now().duration_since(UNIX_EPOCH)? })
+    }
+}
+
+impl FrameSink for SavefileSink {
+    fn send(&mut self, frame: &[u8], offset: u64) -> Result<(), Box<dyn Error>> {
+        let ts = self.start + Duration::from_micros(offset);
+        let header = PacketHeader {
+            ts: libc::timeval { tv_sec: ts.as_secs() as _, tv_usec: ts.subsec_micros() as _ },
+            caplen: frame.len() as u32,
+            len: frame.len() as u32,
+        };
+        self.out.write(&Packet::new(&header, frame));
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(self.out.flush()?)
+    }
+}
+
+/// Collects the frames with their offsets for tests.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemorySink {
+    pub frames: Vec<(u64, Vec<u8>)>,
+}
+
+#[cfg(test)]
+impl FrameSink for MemorySink {
+    fn send(&mut self, frame: &[u8], offset: u64) -> Result<(), Box<dyn Error>> {
+        self.frames.push((offset, frame.to_vec()));
+        Ok(())
+    }
+}
+
+/// Writes one line per frame with its offset in µs and the frame as hexadecimal, e.g. to stdout for dry runs.
+pub struct HexSink<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> HexSink<W> {
+    pub fn new(writer: W) -> Self {
+        HexSink { writer }
+    }
+}
+
+impl<W: Write> FrameSink for HexSink<W> {
+    fn send(&mut self, frame: &[u8], offset: u64) -> Result<(), Box<dyn Error>> {
+        let hex: String = frame.iter().map(|b| format!("{:02x}", b)).collect();
+        writeln!(self.writer, "{} {}", offset, hex)?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(self.writer.flush()?)
+    }
+}
+
+/// Opens the sink selected on the command line, a savefile or stdout take precedence over the device.
+///
+/// # Arguments
+///
+/// * `device` - The network device name to send on.
+/// * `address` - The [MacAddress] of the distribution, which is only logged.
+/// * `pcap_out` - A [PathBuf] representing the path to the pcap savefile to write instead of sending.
+/// * `hex` - Print the frames as hexadecimal to stdout instead of sending.
+///
+pub fn open_sink(device: Option<String>, address: MacAddress, pcap_out: Option<PathBuf>, hex: bool) -> Result<Box<dyn FrameSink>, Box<dyn Error>> {
+    match (pcap_out, hex, device) {
+        (Some(p), _, _) => Ok(Box::new(SavefileSink::create(p)?)),
+        (None, true, _) => Ok(Box::new(HexSink::new(std::io::stdout().lock()))),
+        (None, false, Some(d)) => Ok(Box::new(LiveSink::open(&d, address)?)),
+        (None, false, None) => Err("A device is required for the distribution".into()),
+    }
+}
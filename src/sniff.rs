@@ -0,0 +1,323 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+
+use pcap::Linktype;
+
+use crate::{GGID, MacAddress};
+use crate::error::err_reason;
+use crate::frame::{FrameError, HEADER_FRAGMENT_INDEX, NintendoBeacon};
+use crate::pcd::{Encrypted, Extended, PCD, PCD_EXTENDED_LENGTH, PCD_FRAGMENTS, PCD_HEADER_LENGTH, PCD_LENGTH, PCDFragment, Partitioned};
+use crate::radiotap;
+use crate::session;
+
+/// Time in ms after which a live capture returns without packet, to check for signals.
+const CAPTURE_TIMEOUT: i32 = 100;
+
+/// A fragment of a distribution read from a beacon frame.
+pub struct BeaconFragment {
+    pub id: DistributionId,
+    /// Index of the encrypted fragment, or [HEADER_FRAGMENT_INDEX] for the plaintext header.
+    pub index: u16,
+    pub payload: PCDFragment,
+}
+
+/// Identifies a distribution, each card is distributed by one address and encrypted with its checksum.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DistributionId {
+    pub address: MacAddress,
+    pub checksum: u16,
+    /// The raw GGID, which may be unknown.
+    pub ggid: u32,
+}
+
+impl DistributionId {
+    pub fn region(&self) -> Option<GGID> {
+        GGID::try_from(self.ggid).ok()
+    }
+}
+
+impl DistributionId {
+    /// A file name of the card, e.g. `en_a4c0e16e7680_1cb4.pcd`, the raw GGID is used for unknown regions.
+    pub fn file_name(&self) -> String {
+        let region = self.region().map(|r| r.to_string()).unwrap_or_else(|| format!("{:x}", self.ggid));
+        let address: String = self.address.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("{}_{}_{:04x}.pcd", region, address, self.checksum)
+    }
+}
+
+impl Display for DistributionId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let address = self.address.iter().map(|b| format!("{:02x}", b)).collect::<Vec<String>>().join(":");
+        match self.region() {
+            Some(r) => write!(f, "address {} region '{}' checksum {:04x}", address, r, self.checksum),
+            None => write!(f, "address {} GGID {:x} checksum {:04x}", address, self.ggid, self.checksum),
+        }
+    }
+}
+
+/// Parses a captured frame of a distribution.
+///
+/// # Arguments
+///
+/// * `frame` - The captured frame.
+/// * `linktype` - The [Linktype] of the capture, the frame is either preceded by a radiotap header or not.
+///
+/// # Returns
+///
+/// Returns the [BeaconFragment] or the [FrameError] if the frame is no valid beacon frame of a distribution.
+///
+pub fn parse_fragment(frame: &[u8], linktype: Linktype) -> Result<BeaconFragment, FrameError> {
+    let (frame, fcs) = match linktype {
+        Linktype::IEEE802_11_RADIOTAP => radiotap::strip(frame).ok_or(FrameError::Truncated)?,
+        Linktype::IEEE802_11 => (frame, false),
+        _ => return Err(FrameError::NoDistribution),
+    };
+    let beacon = NintendoBeacon::parse(frame, fcs)?;
+    let packet = beacon.packet;
+    if packet.payload_length != PCD_EXTENDED_LENGTH as u32 || (packet.fragment_index as usize >= PCD_FRAGMENTS - 1 && packet.fragment_index != HEADER_FRAGMENT_INDEX) {
+        return Err(FrameError::NoDistribution);
+    }
+    Ok(BeaconFragment {
+        id: DistributionId { address: beacon.source, checksum: packet.checksum, ggid: packet.ggid },
+        index: packet.fragment_index,
+        payload: packet.payload,
+    })
+}
+
+/// The fragments of a single distribution received so far.
+#[derive(Default)]
+pub struct Reassembly {
+    fragments: [Option<PCDFragment>; PCD_FRAGMENTS - 1],
+    header: Option<PCDFragment>,
+}
+
+impl Reassembly {
+    /// Adds the fragment, returns whether it was not received before.
+    pub fn add(&mut self, index: u16, payload: PCDFragment) -> bool {
+        let slot = if index == HEADER_FRAGMENT_INDEX { &mut self.header } else { &mut self.fragments[index as usize] };
+        slot.replace(payload).is_none()
+    }
+
+    /// The amount of distinct fragments received, including the header fragment.
+    pub fn received(&self) -> usize {
+        self.fragments.iter().chain([&self.header]).filter(|f| f.is_some()).count()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.received() == PCD_FRAGMENTS
+    }
+
+    /// Decrypts the complete distribution and verifies it against the plaintext header and the checksum.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The [DistributionId] the fragments were received with.
+    ///
+    /// # Returns
+    ///
+    /// Returns the decrypted [PCD<Partitioned>],
+    /// or an error message if fragments are missing or the decrypted data does not match.
+    ///
+    pub fn assemble(&self, id: &DistributionId) -> Result<PCD<Partitioned>, String> {
+        let (Some(fragments), Some(header)) = (self.fragments.iter().copied().collect::<Option<Vec<PCDFragment>>>(), self.header) else {
+            return Err(format!("Only {} of {} fragments were received", self.received(), PCD_FRAGMENTS));
+        };
+        let encrypted: PCD<Encrypted> = PCD::try_from(fragments.concat().as_slice())?;
+        let extended: PCD<Extended> = encrypted.decrypt(&id.address, id.checksum);
+        extended.verify(id.checksum).map_err(|e| format!("The decrypted data is invalid: {}", e))?;
+        let partitioned = extended.simplify();
+        if partitioned.header() != header[..PCD_HEADER_LENGTH] {
+            return Err("The decrypted header does not match the plaintext header fragment".to_string());
+        }
+        Ok(partitioned)
+    }
+}
+
+/// Collects the fragments of all distributions, told apart by their [DistributionId].
+#[derive(Default)]
+pub struct Reassembler {
+    distributions: HashMap<DistributionId, Reassembly>,
+    /// The distributions in the order they were found.
+    order: Vec<DistributionId>,
+}
+
+impl Reassembler {
+    /// Adds the fragment and returns the [Reassembly] of its distribution, if the fragment was not received before.
+    pub fn add(&mut self, fragment: BeaconFragment) -> Option<&Reassembly> {
+        let reassembly = self.distributions.entry(fragment.id).or_insert_with(|| {
+            self.order.push(fragment.id);
+            Reassembly::default()
+        });
+        reassembly.add(fragment.index, fragment.payload).then_some(reassembly)
+    }
+
+    /// All distributions in the order they were found.
+    pub fn distributions(&self) -> impl Iterator<Item=(&DistributionId, &Reassembly)> {
+        self.order.iter().map(|id| (id, &self.distributions[id]))
+    }
+}
+
+/// Captures a distribution on a monitor mode device and writes the decrypted card to a PCD file.
+///
+/// Fragments are collected until every fragment of one distribution was received,
+/// the first complete distribution which can be decrypted is written.
+///
+/// # Arguments
+///
+/// * `device` - A [String] representing the network device name in monitor mode, tuned to the channel of the distribution.
+/// * `region` - Only capture the distribution of this [GGID] if given.
+/// * `pcd_file` - A [PathBuf] representing the path to the output decrypted PCD file.
+///
+/// # Returns
+///
+/// Returns [Ok(())] if a card was captured and written,
+/// otherwise returns an error wrapped in a [Box<dyn Error>], e.g. if the capture was interrupted by a signal.
+///
+pub fn sniff(device: String, region: Option<GGID>, pcd_file: PathBuf) -> Result<(), Box<dyn Error>> {
+    let shutdown = session::shutdown_flag()?;
+    let mut cap = pcap::Capture::from_device(device.as_str())?.timeout(CAPTURE_TIMEOUT).open()?;
+    let linktype = cap.get_datalink();
+    if linktype != Linktype::IEEE802_11_RADIOTAP && linktype != Linktype::IEEE802_11 {
+        return Err(format!("Device '{}' captures {:?} instead of 802.11 frames, is it in monitor mode?", device, linktype).into());
+    }
+    eprintln!("Listening for distributions on device '{}'...", device);
+    let mut reassembler = Reassembler::default();
+    while !shutdown.load(Ordering::SeqCst) {
+        let packet = match cap.next_packet() {
+            Ok(p) => p,
+            Err(pcap::Error::TimeoutExpired) => continue,
+            Err(e) => return Err(err_reason("Unable to capture", e).into()),
+        };
+        let Ok(fragment) = parse_fragment(packet.data, linktype) else { continue };
+        if region.is_some_and(|r| fragment.id.region() != Some(r)) {
+            continue;
+        }
+        let id = fragment.id;
+        let Some(reassembly) = reassembler.add(fragment) else { continue };
+        match reassembly.received() {
+            1 => eprintln!("Found distribution with {}", id),
+            n => eprintln!("Received {}/{} fragments of the distribution with {}", n, PCD_FRAGMENTS, id),
+        }
+        if !reassembly.is_complete() {
+            continue;
+        }
+        match reassembly.assemble(&id) {
+            Ok(pcd) => {
+                let data: [u8; PCD_LENGTH] = PCD::from(&pcd).into();
+                fs::write(&pcd_file, data).map_err(|e| err_reason("Unable to write pcd file", e))?;
+                eprintln!("Wrote the card of the distribution with {} to '{}'", id, pcd_file.display());
+                return Ok(());
+            }
+            Err(e) => eprintln!("Warning: unable to decrypt the distribution with {}: {}", id, e),
+        }
+    }
+    Err("Interrupted before a distribution was received completely".into())
+}
+
+/// Extracts every distribution of a pcap or pcapng capture and writes the decrypted cards to PCD files.
+///
+/// Distributions are told apart by address, checksum and GGID, which make up the file names.
+/// Incomplete distributions and distributions which fail the verification are skipped with a warning.
+///
+/// # Arguments
+///
+/// * `capture` - A [PathBuf] representing the path to the capture with 802.11 frames, with or without radiotap header.
+/// * `output` - A [PathBuf] representing the directory to write the PCD files to, created if it does not exist.
+///
+/// # Returns
+///
+/// Returns [Ok(())] if at least one card was extracted,
+/// otherwise returns an error wrapped in a [Box<dyn Error>].
+///
+pub fn extract(capture: PathBuf, output: PathBuf) -> Result<(), Box<dyn Error>> {
+    let mut cap = pcap::Capture::from_file(&capture).map_err(|e| err_reason("Unable to open capture", e))?;
+    let linktype = cap.get_datalink();
+    if linktype != Linktype::IEEE802_11_RADIOTAP && linktype != Linktype::IEEE802_11 {
+        return Err(format!("The capture contains {:?} instead of 802.11 frames", linktype).into());
+    }
+    let mut reassembler = Reassembler::default();
+    let mut frames = 0;
+    let mut corrupted = 0;
+    loop {
+        let packet = match cap.next_packet() {
+            Ok(p) => p,
+            Err(pcap::Error::NoMorePackets) => break,
+            Err(e) => return Err(err_reason("Unable to read capture", e).into()),
+        };
+        frames += 1;
+        match parse_fragment(packet.data, linktype) {
+            Ok(fragment) => {
+                reassembler.add(fragment);
+            }
+            Err(FrameError::InvalidFcs { .. }) => corrupted += 1,
+            Err(_) => {}
+        }
+    }
+    eprintln!("Read {} frames from '{}', {} of them with invalid FCS", frames, capture.display(), corrupted);
+    fs::create_dir_all(&output).map_err(|e| err_reason("Unable to create output directory", e))?;
+    let mut extracted = 0;
+    for (id, reassembly) in reassembler.distributions() {
+        match reassembly.assemble(id) {
+            Ok(pcd) => {
+                let file = output.join(id.file_name());
+                let data: [u8; PCD_LENGTH] = PCD::from(&pcd).into();
+                fs::write(&file, data).map_err(|e| err_reason("Unable to write pcd file", e))?;
+                eprintln!("Extracted the card '{}' of the distribution with {} to '{}'", pcd.deserialize().state.title, id, file.display());
+                extracted += 1;
+            }
+            Err(e) => eprintln!("Warning: skipping the distribution with {}: {}", id, e),
+        }
+    }
+    if extracted == 0 {
+        return Err("No complete distribution found in the capture".into());
+    }
+    eprintln!("Extracted {} cards", extracted);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::beacon::{TEST_ADDRESS, test_generator_with};
+    use crate::pcd::{CardType, PCD_CARD_TYPE_OFFSET, PCD_TITLE_OFFSET, Raw};
+
+    #[test]
+    fn reassemble_generated_distribution() {
+        let address = TEST_ADDRESS;
+        let mut data = [0u8; PCD_LENGTH];
+        let mut seed = 0x1234_5678u32;
+        for b in data.iter_mut() {
+            seed = seed.wrapping_mul(0x41c64e6d).wrapping_add(0x6073);
+            *b = (seed >> 24) as u8;
+        }
+        // valid card type and title terminator, otherwise the verification fails
+        data[PCD_CARD_TYPE_OFFSET] = CardType::Item as u8;
+        data[PCD_TITLE_OFFSET + 8..PCD_TITLE_OFFSET + 10].copy_from_slice(&[0xff, 0xff]);
+        let partitioned: PCD<Partitioned> = PCD::<Raw>::try_from(data.as_slice()).unwrap().into();
+        let checksum = PCD::<Extended>::from(partitioned).checksum().unwrap();
+        let generator = test_generator_with(&data, GGID::German);
+
+        let mut reassembler = Reassembler::default();
+        let mut assembled = None;
+        // start in the middle of a round, as a capture would
+        for frame in generator.skip(4).take(PCD_FRAGMENTS) {
+            let fragment = parse_fragment(&frame, Linktype::IEEE802_11_RADIOTAP).unwrap();
+            assert_eq!(DistributionId { address, checksum, ggid: GGID::German as u32 }, fragment.id);
+            let id = fragment.id;
+            let reassembly = reassembler.add(fragment).unwrap();
+            if reassembly.is_complete() {
+                let wrong = DistributionId { checksum: checksum ^ 1, ..id };
+                assert!(reassembly.assemble(&wrong).is_err());
+                assembled = Some(PCD::<Raw>::from(&reassembly.assemble(&id).unwrap()));
+            }
+        }
+        let assembled: [u8; PCD_LENGTH] = assembled.unwrap().into();
+        assert_eq!(data, assembled);
+    }
+}